@@ -1,24 +1,320 @@
 use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Args;
-use feastore::Store;
+use feastore::{Format, Manifest, Store};
+use itertools::Itertools;
 
 #[derive(Debug, Args)]
 #[command(args_conflicts_with_subcommands = true)]
 pub struct ApplyCmd {
-    /// filepath
-    #[arg(short, long)]
-    filepath: String,
+    /// manifest file, '-' for stdin, directory or glob like 'registry/**/*.yaml', can be
+    /// repeated; the manifest files in a directory are read recursively, all applied as one
+    #[arg(short, long, required = true)]
+    filepath: Vec<String>,
+
+    /// format of stdin and the manifest files given explicitly, told by their extensions
+    /// by default; the files in a directory or matched by a glob are always told so
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+
+    /// delete the entities, groups and features absent from the file, with their data
+    #[arg(long)]
+    prune: bool,
+
+    /// delete the pruned objects without confirmation
+    #[arg(short, long, requires = "prune")]
+    yes: bool,
+
+    /// print the plan of changes without applying it
+    #[arg(long)]
+    dry_run: bool,
 }
 
 impl ApplyCmd {
     pub async fn run(self, store: Store) -> Result<()> {
-        let reader = fs::OpenOptions::new()
-            .read(true)
-            .open(&self.filepath)
-            .context("config file open failed.")?;
+        let manifests = read_manifests(&self.filepath, self.format)?;
+        let plan = store.plan_manifests(&manifests, self.prune).await?;
+        if self.dry_run {
+            print!("{plan}");
+            return Ok(());
+        }
+
+        // a plan having conflicts fails anyway
+        if !plan.prune.is_empty() && plan.conflicts.is_empty() && !self.yes {
+            print!("{plan}");
+            if !confirm("Delete them along with their data?")? {
+                bail!("apply aborted.");
+            }
+        }
+
+        store.apply_plan(&plan).await?;
+        for (kind, names) in [
+            ("entity", &plan.prune.entities),
+            ("group", &plan.prune.groups),
+            ("feature", &plan.prune.features),
+        ] {
+            for name in names {
+                println!("deleted {kind} '{name}'");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Asks a yes or no question on the terminal, no by default.
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Reads the manifest files of the paths, `-` for stdin, a directory for its yaml, json
+/// and toml files and a glob for the files it matches. A file found twice is read once.
+/// `format` applies to stdin and the files given explicitly, the others are told by
+/// their extensions.
+fn read_manifests(paths: &[String], format: Option<Format>) -> Result<Vec<Manifest>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let found: Vec<(PathBuf, Option<Format>)> = if path == "-" {
+            vec![(PathBuf::from(path), format)]
+        } else if is_glob(path) {
+            glob(path)?
+                .into_iter()
+                .map(|f| {
+                    let format = Format::from_path(&f);
+                    (f, format)
+                })
+                .collect()
+        } else if Path::new(path).is_dir() {
+            let mut found = Vec::new();
+            walk(Path::new(path), &mut found)?;
+            found
+                .into_iter()
+                .filter_map(|f| Format::from_path(&f).map(|format| (f, Some(format))))
+                .collect()
+        } else {
+            let format = format.or_else(|| Format::from_path(path));
+            vec![(PathBuf::from(path), format)]
+        };
+        if found.is_empty() {
+            bail!("no manifest file found in '{path}'.");
+        }
+        files.extend(found);
+    }
+
+    files
+        .into_iter()
+        .unique_by(|(file, _)| file.clone())
+        .map(|(file, format)| {
+            let content = if file == Path::new("-") {
+                io::read_to_string(io::stdin()).context("read stdin failed.")?
+            } else {
+                fs::read_to_string(&file)
+                    .with_context(|| format!("config file {} open failed.", file.display()))?
+            };
+            Ok(Manifest {
+                path: file.display().to_string(),
+                content,
+                format: format.unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+/// Lists the entries of a directory in order of their names, joined to `dir`,
+/// along with whether each is a directory. A symbolic link is never a directory.
+fn read_dir(dir: &Path) -> Result<Vec<(PathBuf, bool)>> {
+    let read = if dir.as_os_str().is_empty() {
+        fs::read_dir(".")
+    } else {
+        fs::read_dir(dir)
+    };
+    let mut entries = read
+        .with_context(|| format!("read directory {} failed.", dir.display()))?
+        .map(|entry| {
+            let entry = entry?;
+            Ok((dir.join(entry.file_name()), entry.file_type()?.is_dir()))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// Lists the files under a directory recursively, in order of their paths.
+/// Symbolic links to directories are not followed.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for (path, is_dir) in read_dir(dir)? {
+        if is_dir {
+            walk(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Lists a directory and the directories under it recursively.
+fn walk_dirs(dir: &Path, dirs: &mut Vec<PathBuf>) -> Result<()> {
+    dirs.push(dir.to_path_buf());
+    for (path, is_dir) in read_dir(dir)? {
+        if is_dir {
+            walk_dirs(&path, dirs)?;
+        }
+    }
+    Ok(())
+}
+
+/// Lists the files matching a pattern, where `*` and `?` match within a path
+/// component, and `**` matches any number of directories. Only `**` descends into
+/// subdirectories, and symbolic links to directories are not followed by wildcards.
+fn glob(pattern: &str) -> Result<Vec<PathBuf>> {
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    let mut paths = vec![match pattern.starts_with('/') {
+        true => PathBuf::from("/"),
+        false => PathBuf::new(),
+    }];
+
+    for (i, &component) in components.iter().enumerate() {
+        let last = i + 1 == components.len();
+        let mut matched = Vec::new();
+        for dir in &paths {
+            if component == "**" {
+                match last {
+                    true => walk(dir, &mut matched)?,
+                    false => walk_dirs(dir, &mut matched)?,
+                }
+            } else if !is_glob(component) {
+                let path = dir.join(component);
+                if (last && path.is_file()) || (!last && path.is_dir()) {
+                    matched.push(path);
+                }
+            } else {
+                let component: Vec<char> = component.chars().collect();
+                for (path, is_dir) in read_dir(dir)? {
+                    let name: Vec<char> = match path.file_name() {
+                        Some(name) => name.to_string_lossy().chars().collect(),
+                        None => continue,
+                    };
+                    let wanted = match last {
+                        true => path.is_file(),
+                        false => is_dir,
+                    };
+                    if wanted && matches_name(&component, &name) {
+                        matched.push(path);
+                    }
+                }
+            }
+        }
+        // several `**` could reach a directory more than once
+        matched.sort();
+        matched.dedup();
+        paths = matched;
+    }
+    Ok(paths)
+}
+
+/// Matches a path component against a pattern of `*` and `?`, backtracking only to
+/// the last `*`, so it takes at most the product of their lengths.
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // where the last `*` is in the pattern, and where it started to match in the name
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // let the last `*` match one more character
+                Some((sp, sn)) => {
+                    star = Some((sp, sn + 1));
+                    p = sp + 1;
+                    n = sn + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_name() {
+        let cases = [
+            ("*.yaml", "device.yaml", true),
+            ("*.yaml", "device.json", false),
+            ("dev?ce.y*ml", "device.yml", true),
+            ("dev?ce.y*ml", "devce.yml", false),
+            ("*a*b", "xaybab", true),
+            ("*a*b", "xaybaba", false),
+            ("**", "", true),
+        ];
+        for (pattern, name, want) in cases {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            assert_eq!(matches_name(&pattern, &name), want, "{pattern:?} {name:?}");
+        }
+
+        // many stars against a long name that never matches
+        let pattern: Vec<char> = "*a".repeat(30).chars().chain(['b']).collect();
+        let name = vec!['a'; 100];
+        assert!(!matches_name(&pattern, &name));
+    }
+
+    #[test]
+    fn test_glob() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        for file in [
+            "device.yaml",
+            "a/user.yaml",
+            "a/b/click.yaml",
+            "a/b/click.json",
+        ] {
+            let file = root.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "").unwrap();
+        }
+        // a link back to the root is never followed
+        std::os::unix::fs::symlink(root, root.join("a/loop")).unwrap();
 
-        store.apply(reader).await.map_err(|err| err.into())
+        let glob = |pattern: &str| {
+            glob(&format!("{}/{pattern}", root.display()))
+                .unwrap()
+                .into_iter()
+                .map(|f| f.strip_prefix(root).unwrap().display().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(glob("*.yaml"), vec!["device.yaml"]);
+        assert_eq!(glob("*/*.yaml"), vec!["a/user.yaml"]);
+        assert_eq!(
+            glob("**/*.yaml"),
+            vec!["a/b/click.yaml", "a/user.yaml", "device.yaml"]
+        );
+        assert_eq!(
+            glob("a/**"),
+            vec!["a/b/click.json", "a/b/click.yaml", "a/user.yaml"]
+        );
+        assert_eq!(glob("**/b/*.j*"), vec!["a/b/click.json"]);
+        assert_eq!(glob("*/*/*.yaml"), vec!["a/b/click.yaml"]);
     }
 }
@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+
+use feastore::database::metadata::GetOpt;
+use feastore::Store;
+
+#[derive(Args)]
+pub struct DeleteCommand {
+    #[command(subcommand)]
+    cmds: SubCmd,
+}
+
+#[derive(Subcommand)]
+enum SubCmd {
+    /// Delete an entity
+    Entity(DeleteEntity),
+    /// Delete a group, with its online table and offline data
+    Group(DeleteGroup),
+    /// Delete a feature, with its online and offline values
+    Feature(DeleteFeature),
+}
+
+#[derive(Args)]
+struct DeleteEntity {
+    name: String,
+    /// delete the groups and features of the entity as well
+    #[arg(long)]
+    cascade: bool,
+}
+
+#[derive(Args)]
+struct DeleteGroup {
+    name: String,
+    /// delete the features of the group as well
+    #[arg(long)]
+    cascade: bool,
+}
+
+#[derive(Args)]
+struct DeleteFeature {
+    /// full name of the feature, e.g. device.model
+    name: String,
+}
+
+impl DeleteCommand {
+    pub async fn run(self, store: Store) -> Result<()> {
+        match self.cmds {
+            SubCmd::Entity(entity) => delete_entity(entity, store).await,
+            SubCmd::Group(group) => delete_group(group, store).await,
+            SubCmd::Feature(feature) => delete_feature(feature, store).await,
+        }
+    }
+}
+
+async fn delete_entity(entity: DeleteEntity, store: Store) -> Result<()> {
+    let entity_id = store
+        .get_entity(GetOpt::Name(&entity.name))
+        .await?
+        .ok_or_else(|| anyhow!("entity '{}' not found", entity.name))?
+        .id;
+
+    store.delete_entity(entity_id, entity.cascade).await?;
+    println!("deleted entity '{}'", entity.name);
+    Ok(())
+}
+
+async fn delete_group(group: DeleteGroup, store: Store) -> Result<()> {
+    let group_id = store
+        .get_group(GetOpt::Name(&group.name))
+        .await?
+        .ok_or_else(|| anyhow!("group '{}' not found", group.name))?
+        .id;
+
+    store.delete_group(group_id, group.cascade).await?;
+    println!("deleted group '{}'", group.name);
+    Ok(())
+}
+
+async fn delete_feature(feature: DeleteFeature, store: Store) -> Result<()> {
+    let feature_id = store
+        .list_feature(std::slice::from_ref(&feature.name))
+        .await?
+        .first()
+        .ok_or_else(|| anyhow!("feature '{}' not found", feature.name))?
+        .id;
+
+    store.delete_feature(feature_id).await?;
+    println!("deleted feature '{}'", feature.name);
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use anyhow::{Context, Result};
+use clap::Args;
+
+use feastore::{ExportOpt, Store};
+
+#[derive(Debug, Args)]
+pub struct ExportCmd {
+    /// group name
+    #[arg(short, long)]
+    group: String,
+
+    /// unix milliseconds to export the group as of, a batch group is exported
+    /// at its latest revision not after it; defaults to now
+    #[arg(long)]
+    unix_milli: Option<i64>,
+
+    /// names of the features to export, e.g. model,price; defaults to all
+    #[arg(long, value_delimiter = ',')]
+    features: Vec<String>,
+
+    /// file to write the rows to as CSV, defaults to stdout
+    #[arg(long)]
+    output_file: Option<String>,
+}
+
+impl ExportCmd {
+    pub async fn run(self, store: Store) -> Result<()> {
+        let res = store
+            .export(ExportOpt {
+                group_name: self.group,
+                features: self.features,
+                unix_milli: self.unix_milli,
+            })
+            .await?;
+
+        let w: Box<dyn Write + Send> = match self.output_file {
+            Some(ref path) => Box::new(BufWriter::new(
+                File::create(path).context("output file create failed.")?,
+            )),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        res.write_csv(w).await?;
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
+use std::iter;
+
 use anyhow::Result;
 use clap::{Args, Subcommand};
 use csv::Writer;
+use itertools::Itertools;
 use prettytable::Table;
 use serde::Serialize;
+use serde_yaml::Mapping;
 
 use feastore::database::metadata::ListOpt;
 use feastore::Store;
@@ -30,6 +34,19 @@ enum SubCmd {
     Group,
     /// Get existing features given specific conditions
     Feature,
+    /// Get online feature values of entity keys
+    Online(OnlineArgs),
+}
+
+#[derive(Debug, Args)]
+struct OnlineArgs {
+    /// entity keys, e.g. 1,2
+    #[arg(long, value_delimiter = ',', required = true)]
+    entity_key: Vec<String>,
+
+    /// full names of the features, e.g. device.model,device.price
+    #[arg(long, value_delimiter = ',', required = true)]
+    features: Vec<String>,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -45,6 +62,7 @@ impl Command {
             SubCmd::Entity => self.get_entity(store).await,
             SubCmd::Group => self.get_group(store).await,
             SubCmd::Feature => self.get_feature(store).await,
+            SubCmd::Online(args) => self.get_online(store, args).await,
         }
     }
 
@@ -91,6 +109,53 @@ impl Command {
         }
         Ok(())
     }
+
+    async fn get_online(&self, store: Store, args: &OnlineArgs) -> Result<()> {
+        let mut values = store
+            .get_online_features_multi(&args.entity_key, &args.features)
+            .await?;
+        let rows = args
+            .entity_key
+            .iter()
+            .unique()
+            .filter_map(|key| values.remove(key));
+
+        match &self.output_format {
+            Format::Yaml => {
+                let rows = rows
+                    .map(|row| {
+                        let mut mapping = Mapping::new();
+                        mapping.insert("entity_key".into(), row.entity_key.into());
+                        for (name, value) in row.features.into_iter().zip(row.values) {
+                            mapping.insert(name.into(), serde_yaml::to_value(value)?);
+                        }
+                        Ok(mapping)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                output(rows, &Format::Yaml);
+            }
+            format => {
+                let header = iter::once("entity_key".to_owned())
+                    .chain(args.features.iter().cloned())
+                    .collect();
+                let records =
+                    rows.map(|row| {
+                        iter::once(row.entity_key)
+                            .chain(row.values.iter().map(|value| {
+                                value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+                            }))
+                            .collect()
+                    });
+                output(
+                    iter::once(header)
+                        .chain(records)
+                        .collect::<Vec<Vec<String>>>(),
+                    format,
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Serialize)]
@@ -133,7 +198,7 @@ fn to_csv_string<S: Serialize>(serize: Vec<S>) -> String {
     String::from_utf8(wtr.into_inner().unwrap()).unwrap()
 }
 
-fn build_opt(names: &Vec<String>) -> ListOpt {
+fn build_opt(names: &Vec<String>) -> ListOpt<'_> {
     if names.is_empty() {
         ListOpt::All
     } else {
@@ -0,0 +1,423 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use chrono::DateTime;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use tonic::{Code, Request, Response, Status};
+
+use feastore::database::metadata::FeatureValue;
+use feastore::{EntityRow, Error, FeatureValues, JoinOpt, PushOpt, Store};
+
+#[allow(clippy::enum_variant_names)]
+pub mod proto {
+    tonic::include_proto!("feastore");
+}
+
+use proto::feature_store_server::FeatureStore;
+pub use proto::feature_store_server::FeatureStoreServer;
+use proto::value::Kind;
+
+/// Serves the gRPC API on top of a store.
+pub struct FeatureStoreService {
+    store: Arc<Store>,
+}
+
+impl FeatureStoreService {
+    pub fn new(store: Arc<Store>) -> Self {
+        Self { store }
+    }
+}
+
+#[tonic::async_trait]
+impl FeatureStore for FeatureStoreService {
+    async fn online_get(
+        &self,
+        request: Request<proto::OnlineGetRequest>,
+    ) -> Result<Response<proto::OnlineGetResponse>, Status> {
+        let req = request.into_inner();
+        let values = self
+            .store
+            .get_online_features(&req.entity_key, &req.features)
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(proto::OnlineGetResponse {
+            values: Some(values.into()),
+        }))
+    }
+
+    async fn online_multi_get(
+        &self,
+        request: Request<proto::OnlineMultiGetRequest>,
+    ) -> Result<Response<proto::OnlineMultiGetResponse>, Status> {
+        let req = request.into_inner();
+        let values = self
+            .store
+            .get_online_features_multi(&req.entity_keys, &req.features)
+            .await
+            .map_err(status)?;
+        let values = req
+            .entity_keys
+            .iter()
+            .filter_map(|key| values.get(key).cloned())
+            .map(proto::FeatureValues::from)
+            .collect();
+
+        Ok(Response::new(proto::OnlineMultiGetResponse { values }))
+    }
+
+    async fn push(
+        &self,
+        request: Request<proto::PushRequest>,
+    ) -> Result<Response<proto::PushResponse>, Status> {
+        let req = request.into_inner();
+        let values = req
+            .values
+            .into_iter()
+            .map(from_proto)
+            .collect::<feastore::Result<_>>()
+            .map_err(status)?;
+        self.store
+            .push(PushOpt {
+                entity_key: req.entity_key,
+                group_name: req.group,
+                features: req.features,
+                values,
+                unix_milli: req.unix_milli,
+            })
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(proto::PushResponse {}))
+    }
+
+    type JoinStream = BoxStream<'static, Result<proto::JoinResponse, Status>>;
+
+    async fn join(
+        &self,
+        request: Request<proto::JoinRequest>,
+    ) -> Result<Response<Self::JoinStream>, Status> {
+        let req = request.into_inner();
+        let entity_rows = req.entity_rows.into_iter().map(|row| {
+            Ok::<_, Infallible>(EntityRow {
+                entity_key: row.entity_key,
+                unix_milli: row.unix_milli,
+                values: row.values,
+            })
+        });
+        let res = self
+            .store
+            .join(
+                JoinOpt {
+                    features: req.features,
+                    value_names: req.value_names,
+                },
+                entity_rows,
+            )
+            .await
+            .map_err(status)?;
+
+        let rows = res
+            .rows
+            .map_ok(|row| proto::JoinResponse {
+                values: row.into_iter().map(to_proto).collect(),
+            })
+            .map_err(status);
+        Ok(Response::new(rows.boxed()))
+    }
+
+    async fn sync(
+        &self,
+        request: Request<proto::SyncRequest>,
+    ) -> Result<Response<proto::SyncResponse>, Status> {
+        let req = request.into_inner();
+        let res = self
+            .store
+            .sync(&req.group, req.revision)
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(proto::SyncResponse {
+            revision_id: res.revision_id,
+            revision: res.revision,
+            rows: res.rows,
+        }))
+    }
+}
+
+fn status(err: Error) -> Status {
+    Status::new(code(&err), err.to_string())
+}
+
+fn code(err: &Error) -> Code {
+    match err {
+        Error::DataNotFound(_) => Code::NotFound,
+        Error::DataExist(_) => Code::AlreadyExists,
+        Error::InvalidValue(_) | Error::Validation(_) => Code::InvalidArgument,
+        Error::SqlxError(_) | Error::Error(_) => Code::Internal,
+        Error::Conflict(_) => Code::FailedPrecondition,
+        Error::Apply(err) => code(&err.source),
+    }
+}
+
+impl From<FeatureValues> for proto::FeatureValues {
+    fn from(values: FeatureValues) -> Self {
+        Self {
+            entity_key: values.entity_key,
+            features: values.features,
+            values: values.values.into_iter().map(to_proto).collect(),
+        }
+    }
+}
+
+fn to_proto(value: Option<FeatureValue>) -> proto::Value {
+    let kind = value.map(|value| match value {
+        FeatureValue::String(v) => Kind::StringValue(v),
+        FeatureValue::Int64(v) => Kind::Int64Value(v),
+        FeatureValue::Float64(v) => Kind::Float64Value(v),
+        FeatureValue::Bool(v) => Kind::BoolValue(v),
+        FeatureValue::Time(v) => Kind::TimeValue(prost_types::Timestamp {
+            seconds: v.timestamp(),
+            nanos: v.timestamp_subsec_nanos() as i32,
+        }),
+        FeatureValue::Bytes(v) => Kind::BytesValue(v),
+    });
+    proto::Value { kind }
+}
+
+fn from_proto(value: proto::Value) -> feastore::Result<Option<FeatureValue>> {
+    let value = match value.kind {
+        None => return Ok(None),
+        Some(Kind::StringValue(v)) => FeatureValue::String(v),
+        Some(Kind::Int64Value(v)) => FeatureValue::Int64(v),
+        Some(Kind::Float64Value(v)) => FeatureValue::Float64(v),
+        Some(Kind::BoolValue(v)) => FeatureValue::Bool(v),
+        Some(Kind::TimeValue(v)) => {
+            let time = u32::try_from(v.nanos)
+                .ok()
+                .and_then(|nanos| DateTime::from_timestamp(v.seconds, nanos))
+                .ok_or_else(|| Error::InvalidValue(format!("invalid timestamp {v}")))?;
+            FeatureValue::Time(time)
+        }
+        Some(Kind::BytesValue(v)) => FeatureValue::Bytes(v),
+    };
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use feastore::database::SQLiteOpt;
+    use feastore::{BackendOpt, FeatureStoreConfig, ImportOpt, Row};
+    use tempfile::TempDir;
+
+    const METADATA: &str = r#"
+kind: Entity
+name: device
+description: 'device'
+---
+kind: Entity
+name: user
+description: 'user'
+---
+kind: Group
+name: phone
+entity: device
+category: batch
+description: 'phone'
+---
+kind: Group
+name: click
+entity: user
+category: stream
+snapshot-interval: 1
+description: 'click'
+---
+kind: Feature
+name: price
+group: phone
+value-type: int64
+description: 'price'
+---
+kind: Feature
+name: clicks
+group: click
+value-type: int64
+description: 'clicks'
+"#;
+
+    async fn service(dir: &TempDir) -> FeatureStoreService {
+        let backend = |file: &str| {
+            let db_file = dir.path().join(file);
+            std::fs::File::create(&db_file).unwrap();
+            BackendOpt {
+                sqlite: Some(SQLiteOpt {
+                    db_file: db_file.to_string_lossy().into_owned(),
+                }),
+            }
+        };
+
+        let store = Store::open(FeatureStoreConfig {
+            metadata: backend("metadata.db"),
+            online: Some(backend("online.db")),
+            offline: Some(backend("offline.db")),
+        })
+        .await;
+        store.apply(METADATA.as_bytes()).await.unwrap();
+
+        let rows = [("1", 3999), ("2", 5299)].map(|(key, price)| {
+            Ok::<_, String>(Row {
+                entity_key: key.to_owned(),
+                values: vec![Some(FeatureValue::Int64(price))],
+            })
+        });
+        store
+            .import(
+                ImportOpt {
+                    group_name: "phone".to_owned(),
+                    features: vec!["price".to_owned()],
+                    revision: Some(1000),
+                    description: String::new(),
+                },
+                rows,
+            )
+            .await
+            .unwrap();
+
+        FeatureStoreService::new(Arc::new(store))
+    }
+
+    fn int64(v: i64) -> proto::Value {
+        to_proto(Some(FeatureValue::Int64(v)))
+    }
+
+    fn null() -> proto::Value {
+        to_proto(None)
+    }
+
+    async fn online_get(svc: &FeatureStoreService, key: &str, feature: &str) -> Vec<proto::Value> {
+        svc.online_get(Request::new(proto::OnlineGetRequest {
+            entity_key: key.to_owned(),
+            features: vec![feature.to_owned()],
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .values
+        .unwrap()
+        .values
+    }
+
+    #[tokio::test]
+    async fn sync_and_online_get() {
+        let dir = TempDir::new().unwrap();
+        let svc = service(&dir).await;
+
+        let res = svc
+            .sync(Request::new(proto::SyncRequest {
+                group: "phone".to_owned(),
+                revision: None,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!((res.revision, res.rows), (1000, 2));
+
+        assert_eq!(
+            online_get(&svc, "1", "phone.price").await,
+            vec![int64(3999)]
+        );
+
+        let res = svc
+            .online_multi_get(Request::new(proto::OnlineMultiGetRequest {
+                entity_keys: vec!["2".to_owned(), "3".to_owned(), "2".to_owned()],
+                features: vec!["phone.price".to_owned()],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let values: Vec<_> = res
+            .values
+            .into_iter()
+            .map(|v| (v.entity_key, v.values))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("2".to_owned(), vec![int64(5299)]),
+                ("3".to_owned(), vec![null()]),
+                ("2".to_owned(), vec![int64(5299)]),
+            ]
+        );
+
+        let err = svc
+            .online_get(Request::new(proto::OnlineGetRequest {
+                entity_key: "1".to_owned(),
+                features: vec!["phone.color".to_owned()],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn push() {
+        let dir = TempDir::new().unwrap();
+        let svc = service(&dir).await;
+        let push = |value: proto::Value, unix_milli: i64| {
+            svc.push(Request::new(proto::PushRequest {
+                group: "click".to_owned(),
+                entity_key: "u1".to_owned(),
+                features: vec!["clicks".to_owned()],
+                values: vec![value],
+                unix_milli: Some(unix_milli),
+            }))
+        };
+
+        push(int64(3), 2000).await.unwrap();
+        push(int64(1), 1000).await.unwrap();
+        assert_eq!(online_get(&svc, "u1", "click.clicks").await, vec![int64(3)]);
+
+        let value = proto::Value {
+            kind: Some(Kind::StringValue("many".to_owned())),
+        };
+        let err = push(value, 3000).await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn join() {
+        let dir = TempDir::new().unwrap();
+        let svc = service(&dir).await;
+        let entity_row = |key: &str, unix_milli: i64| proto::EntityRow {
+            entity_key: key.to_owned(),
+            unix_milli,
+            values: vec!["label".to_owned()],
+        };
+
+        let rows: Vec<_> = svc
+            .join(Request::new(proto::JoinRequest {
+                features: vec!["phone.price".to_owned()],
+                value_names: vec!["label".to_owned()],
+                entity_rows: vec![entity_row("1", 500), entity_row("1", 1500)],
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .map_ok(|row| row.values)
+            .try_collect()
+            .await
+            .unwrap();
+
+        let string = |v: &str| to_proto(Some(FeatureValue::String(v.to_owned())));
+        assert_eq!(
+            rows,
+            vec![
+                vec![string("1"), int64(500), string("label"), null()],
+                vec![string("1"), int64(1500), string("label"), int64(3999)],
+            ]
+        );
+    }
+}
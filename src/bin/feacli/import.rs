@@ -0,0 +1,121 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+
+use feastore::database::metadata::{Feature, GetOpt};
+use feastore::{ImportOpt, Row, Store};
+
+#[derive(Debug, Args)]
+pub struct ImportCmd {
+    /// group name
+    #[arg(short, long)]
+    group: String,
+
+    /// CSV file whose first column is the entity key, followed by features of the group.
+    /// An empty field is null, except for a string feature where it is the empty string
+    #[arg(short, long)]
+    input_file: String,
+
+    /// unix milliseconds since when the data takes effect, defaults to now
+    #[arg(short, long)]
+    revision: Option<i64>,
+
+    /// field delimiter of the CSV file
+    #[arg(long, default_value_t = ',')]
+    delimiter: char,
+
+    /// description of the revision
+    #[arg(long, default_value = "")]
+    description: String,
+}
+
+impl ImportCmd {
+    pub async fn run(self, store: Store) -> Result<()> {
+        let group = store
+            .get_group(GetOpt::Name(&self.group))
+            .await?
+            .ok_or_else(|| anyhow!("group '{}' not found", self.group))?;
+
+        let delimiter = u8::try_from(self.delimiter)
+            .map_err(|_| anyhow!("delimiter '{}' is not an ASCII character", self.delimiter))?;
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .from_path(&self.input_file)
+            .context("input file open failed.")?;
+
+        let header = reader.headers().context("read header failed.")?.clone();
+        if header.get(0) != Some(group.entity_name.as_str()) {
+            bail!(
+                "the first column of the header must be the entity key '{}'",
+                group.entity_name
+            );
+        }
+
+        let feature_names: Vec<String> = header.iter().skip(1).map(String::from).collect();
+        let features = features(&store, &group.name, &feature_names).await?;
+
+        let rows = reader.into_records().map(|record| {
+            let record = record.map_err(|e| e.to_string())?;
+            if record.len() != header.len() {
+                return Err(format!(
+                    "expected {} fields at line {}, got {}",
+                    header.len(),
+                    line(&record),
+                    record.len()
+                ));
+            }
+
+            let values = record
+                .iter()
+                .skip(1)
+                .zip(&features)
+                .map(|(cell, feature)| {
+                    feature
+                        .parse_value(cell)
+                        .map_err(|e| format!("line {}: {e}", line(&record)))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+
+            Ok(Row {
+                entity_key: record[0].to_owned(),
+                values,
+            })
+        });
+
+        let opt = ImportOpt {
+            group_name: self.group,
+            features: feature_names.clone(),
+            revision: self.revision,
+            description: self.description,
+        };
+        let res = store.import(opt, rows).await?;
+
+        println!(
+            "imported {} rows into revision {} (id {})",
+            res.rows, res.revision, res.revision_id
+        );
+        Ok(())
+    }
+}
+
+async fn features(store: &Store, group: &str, feature_names: &[String]) -> Result<Vec<Feature>> {
+    let full_names: Vec<String> = feature_names
+        .iter()
+        .map(|name| format!("{group}.{name}"))
+        .collect();
+    let features = store.list_feature(&full_names).await?;
+
+    full_names
+        .iter()
+        .map(|full_name| {
+            features
+                .iter()
+                .find(|f| &f.full_name() == full_name)
+                .cloned()
+                .ok_or_else(|| anyhow!("feature '{full_name}' not found"))
+        })
+        .collect()
+}
+
+fn line(record: &csv::StringRecord) -> u64 {
+    record.position().map(|p| p.line()).unwrap_or_default()
+}
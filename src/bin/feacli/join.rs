@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use arrow_array::{
+    ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use clap::{Args, ValueEnum};
+use futures::TryStreamExt;
+use parquet::arrow::ArrowWriter;
+
+use feastore::database::metadata::{FeatureValue, ValueType};
+use feastore::{EntityRow, JoinOpt, JoinResult, Store};
+
+/// Rows in one record batch of the parquet output.
+const BATCH_SIZE: usize = 8192;
+
+#[derive(Debug, Args)]
+pub struct JoinCmd {
+    /// CSV file of entity rows, whose columns are the entity key, unix milliseconds
+    /// and extra values carried to the output, e.g. labels
+    #[arg(long)]
+    entity_rows: String,
+
+    /// full names of the features to join, e.g. device.model,device.price
+    #[arg(long, value_delimiter = ',', required = true)]
+    features: Vec<String>,
+
+    /// output format
+    #[arg(value_enum, default_value_t = Format::Csv, short, long)]
+    output: Format,
+
+    /// file to write the result to, defaults to stdout
+    #[arg(long)]
+    output_file: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum Format {
+    Csv,
+    Parquet,
+}
+
+impl JoinCmd {
+    pub async fn run(self, store: Store) -> Result<()> {
+        let value_types = self.value_types(&store).await?;
+
+        let mut reader =
+            csv::Reader::from_path(&self.entity_rows).context("entity rows file open failed.")?;
+        let header = reader.headers().context("read header failed.")?.clone();
+        if header.len() < 2 {
+            bail!("the entity rows must have the entity key and unix milliseconds as the first two columns");
+        }
+
+        let entity_rows = reader.into_records().map(|record| {
+            let record = record.map_err(|e| e.to_string())?;
+            let unix_milli = record[1].parse().map_err(|e| {
+                format!(
+                    "invalid unix milliseconds '{}' at line {}: {e}",
+                    &record[1],
+                    record.position().map(|p| p.line()).unwrap_or_default()
+                )
+            })?;
+
+            Ok::<_, String>(EntityRow {
+                entity_key: record[0].to_owned(),
+                unix_milli,
+                values: record.iter().skip(2).map(String::from).collect(),
+            })
+        });
+
+        let value_names: Vec<String> = header.iter().skip(2).map(String::from).collect();
+        let types = [ValueType::StringType, ValueType::Int64]
+            .into_iter()
+            .chain(value_names.iter().map(|_| ValueType::StringType))
+            .chain(value_types)
+            .collect();
+
+        let opt = JoinOpt {
+            features: self.features,
+            value_names,
+        };
+        let result = store.join(opt, entity_rows).await?;
+
+        let w: Box<dyn Write + Send> = match self.output_file {
+            Some(ref path) => Box::new(BufWriter::new(
+                File::create(path).context("output file create failed.")?,
+            )),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        match self.output {
+            Format::Csv => {
+                result.write_csv(w).await?;
+            }
+            Format::Parquet => write_parquet(result, types, w).await?,
+        }
+        Ok(())
+    }
+
+    /// Validates the feature names, and returns their value types in the same order.
+    async fn value_types(&self, store: &Store) -> Result<Vec<ValueType>> {
+        let features = store.list_feature(&self.features).await?;
+
+        self.features
+            .iter()
+            .map(|name| {
+                features
+                    .iter()
+                    .find(|f| &f.full_name() == name)
+                    .map(|f| f.value_type.clone())
+                    .ok_or_else(|| anyhow!("feature '{name}' not found"))
+            })
+            .collect()
+    }
+}
+
+async fn write_parquet(
+    mut result: JoinResult,
+    types: Vec<ValueType>,
+    w: Box<dyn Write + Send>,
+) -> Result<()> {
+    let fields: Vec<Field> = result
+        .header
+        .iter()
+        .zip(&types)
+        .map(|(name, value_type)| Field::new(name, arrow_type(value_type), true))
+        .collect();
+    let schema: SchemaRef = Arc::new(Schema::new(fields));
+    let mut writer = ArrowWriter::try_new(w, schema.clone(), None)?;
+
+    let mut rows = Vec::with_capacity(BATCH_SIZE);
+    loop {
+        let row = result.rows.try_next().await?;
+        let done = row.is_none();
+        rows.extend(row);
+
+        if rows.len() == BATCH_SIZE || (done && !rows.is_empty()) {
+            let columns = types
+                .iter()
+                .enumerate()
+                .map(|(i, value_type)| arrow_array(value_type, rows.iter().map(|r| r[i].as_ref())))
+                .collect();
+            writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+            rows.clear();
+        }
+        if done {
+            break;
+        }
+    }
+
+    writer.close()?;
+    Ok(())
+}
+
+fn arrow_type(value_type: &ValueType) -> DataType {
+    match value_type {
+        ValueType::StringType => DataType::Utf8,
+        ValueType::Int64 => DataType::Int64,
+        ValueType::Float64 => DataType::Float64,
+        ValueType::Bool => DataType::Boolean,
+        ValueType::Time => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        ValueType::Bytes | ValueType::Invalid => DataType::Binary,
+    }
+}
+
+fn arrow_array<'a>(
+    value_type: &ValueType,
+    values: impl Iterator<Item = Option<&'a FeatureValue>>,
+) -> ArrayRef {
+    match value_type {
+        ValueType::StringType => Arc::new(StringArray::from_iter(values.map(|v| match v {
+            Some(FeatureValue::String(v)) => Some(v.as_str()),
+            _ => None,
+        }))),
+        ValueType::Int64 => Arc::new(Int64Array::from_iter(values.map(|v| match v {
+            Some(FeatureValue::Int64(v)) => Some(*v),
+            _ => None,
+        }))),
+        ValueType::Float64 => Arc::new(Float64Array::from_iter(values.map(|v| match v {
+            Some(FeatureValue::Float64(v)) => Some(*v),
+            _ => None,
+        }))),
+        ValueType::Bool => Arc::new(BooleanArray::from_iter(values.map(|v| match v {
+            Some(FeatureValue::Bool(v)) => Some(*v),
+            _ => None,
+        }))),
+        ValueType::Time => Arc::new(
+            TimestampMicrosecondArray::from_iter(values.map(|v| match v {
+                Some(FeatureValue::Time(v)) => Some(v.timestamp_micros()),
+                _ => None,
+            }))
+            .with_timezone("UTC"),
+        ),
+        ValueType::Bytes | ValueType::Invalid => {
+            Arc::new(BinaryArray::from_iter(values.map(|v| match v {
+                Some(FeatureValue::Bytes(v)) => Some(v.as_slice()),
+                _ => None,
+            })))
+        }
+    }
+}
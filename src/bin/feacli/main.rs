@@ -1,6 +1,14 @@
 mod apply;
+mod delete;
+mod export;
 mod get;
+mod grpc;
+mod import;
+mod join;
 mod register;
+mod serve;
+mod snapshot;
+mod sync;
 mod update;
 
 use anyhow::{Context, Result};
@@ -17,8 +25,22 @@ enum Commands {
     Register(register::RegisterCommand),
     /// update a resource
     Update(update::UpdateCommand),
+    /// Delete a resource
+    Delete(delete::DeleteCommand),
     /// Get Resources
     Get(get::Command),
+    /// Import features of a batch group from a CSV file
+    Import(import::ImportCmd),
+    /// Sync a revision of a batch group from the offline store to the online store
+    Sync(sync::SyncCmd),
+    /// Join features to entity rows by point-in-time, e.g. to generate training data
+    Join(join::JoinCmd),
+    /// Compact the log of a stream group into a snapshot
+    Snapshot(snapshot::SnapshotCmd),
+    /// Export the rows of a group as of a point in time from the offline store as CSV
+    Export(export::ExportCmd),
+    /// Serve online reads, pushes and metadata over an HTTP JSON API, and optionally gRPC
+    Serve(serve::ServeCmd),
 }
 
 #[derive(Parser)]
@@ -45,10 +67,17 @@ impl Cli {
             Commands::Apply(cmd) => cmd.run(feastore).await,
             Commands::Register(cmd) => cmd.run(feastore).await,
             Commands::Update(cmd) => cmd.run(feastore).await,
+            Commands::Delete(cmd) => cmd.run(feastore).await,
             Commands::Get(cmd) => cmd.run(feastore).await,
+            Commands::Import(cmd) => cmd.run(feastore).await,
+            Commands::Sync(cmd) => cmd.run(feastore).await,
+            Commands::Join(cmd) => cmd.run(feastore).await,
+            Commands::Snapshot(cmd) => cmd.run(feastore).await,
+            Commands::Export(cmd) => cmd.run(feastore).await,
+            Commands::Serve(cmd) => cmd.run(feastore).await,
         };
 
-        result.map_err(|err| err.into())
+        result
     }
 
     fn get_config(&self) -> Result<FeatureStoreConfig> {
@@ -0,0 +1,272 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::Args;
+use futures::FutureExt;
+use serde::de::DeserializeSeed;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use feastore::database::metadata::{Entity, FeatureValueSeed, GetOpt, Group, ListOpt, RichFeature};
+use feastore::{Error, FeatureValues, PushOpt, Store};
+
+use crate::grpc::{FeatureStoreServer, FeatureStoreService};
+
+#[derive(Debug, Args)]
+pub struct ServeCmd {
+    /// address to serve the HTTP API on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    http_addr: SocketAddr,
+
+    /// address to serve the gRPC API on, not served if it's not given
+    #[arg(long)]
+    grpc_addr: Option<SocketAddr>,
+}
+
+impl ServeCmd {
+    pub async fn run(self, store: Store) -> Result<()> {
+        let store = Arc::new(store);
+        let shutdown = shutdown_signal().shared();
+
+        let listener = tokio::net::TcpListener::bind(self.http_addr)
+            .await
+            .with_context(|| format!("listen on {} failed.", self.http_addr))?;
+        println!("serving HTTP on {}", listener.local_addr()?);
+        let http = async {
+            axum::serve(listener, router(store.clone()))
+                .with_graceful_shutdown(shutdown.clone())
+                .await
+                .context("serve HTTP failed.")
+        };
+
+        let grpc = async {
+            let Some(addr) = self.grpc_addr else {
+                return Ok(());
+            };
+            println!("serving gRPC on {addr}");
+            tonic::transport::Server::builder()
+                .add_service(FeatureStoreServer::new(FeatureStoreService::new(
+                    store.clone(),
+                )))
+                .serve_with_shutdown(addr, shutdown.clone())
+                .await
+                .context("serve gRPC failed.")
+        };
+
+        tokio::try_join!(http, grpc)?;
+        store.close().await;
+        Ok(())
+    }
+}
+
+fn router(store: Arc<Store>) -> Router {
+    Router::new()
+        .route("/online/get", post(online_get))
+        .route("/online/multi-get", post(online_multi_get))
+        .route("/push", post(push))
+        .route("/entities", get(list_entities))
+        .route("/groups", get(list_groups))
+        .route("/features", get(list_features))
+        .with_state(store)
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("install Ctrl+C handler failed");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("install SIGTERM handler failed")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// A failed request, responded as `{"error": "..."}`.
+struct ApiError(Error);
+
+impl From<Error> for ApiError {
+    fn from(err: Error) -> Self {
+        Self(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = status_code(&self.0);
+        (status, Json(json!({ "error": self.0.to_string() }))).into_response()
+    }
+}
+
+fn status_code(err: &Error) -> StatusCode {
+    match err {
+        Error::DataNotFound(_) => StatusCode::NOT_FOUND,
+        Error::DataExist(_) | Error::Conflict(_) => StatusCode::CONFLICT,
+        Error::InvalidValue(_) | Error::Validation(_) => StatusCode::BAD_REQUEST,
+        Error::SqlxError(_) | Error::Error(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        Error::Apply(err) => status_code(&err.source),
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+#[derive(Deserialize)]
+struct OnlineGetRequest {
+    entity_key: String,
+    /// full names of the features, e.g. `device.model`
+    features: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct OnlineMultiGetRequest {
+    entity_keys: Vec<String>,
+    /// full names of the features, e.g. `device.model`
+    features: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PushRequest {
+    group: String,
+    entity_key: String,
+    /// values by names of all features in the group, null for no value
+    features: Map<String, Value>,
+    /// the event time in unix milliseconds, the push time is used if it's not given
+    unix_milli: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    /// comma separated names, all if not given
+    names: Option<String>,
+}
+
+impl ListQuery {
+    fn names(&self) -> Vec<String> {
+        self.names
+            .as_deref()
+            .map(|names| names.split(',').map(String::from).collect())
+            .unwrap_or_default()
+    }
+}
+
+async fn online_get(
+    State(store): State<Arc<Store>>,
+    Json(req): Json<OnlineGetRequest>,
+) -> ApiResult<Json<FeatureValues>> {
+    let values = store
+        .get_online_features(&req.entity_key, &req.features)
+        .await?;
+    Ok(Json(values))
+}
+
+/// Responds the values of each entity key in the order of the request, one entry for
+/// each requested key even if it's repeated.
+async fn online_multi_get(
+    State(store): State<Arc<Store>>,
+    Json(req): Json<OnlineMultiGetRequest>,
+) -> ApiResult<Json<Vec<FeatureValues>>> {
+    let values = store
+        .get_online_features_multi(&req.entity_keys, &req.features)
+        .await?;
+    let values = req
+        .entity_keys
+        .iter()
+        .filter_map(|key| values.get(key).cloned())
+        .collect();
+    Ok(Json(values))
+}
+
+async fn push(
+    State(store): State<Arc<Store>>,
+    Json(req): Json<PushRequest>,
+) -> ApiResult<StatusCode> {
+    let group = store
+        .get_group(GetOpt::Name(&req.group))
+        .await?
+        .ok_or_else(|| Error::DataNotFound(format!("group '{}' not found", req.group)))?;
+    let full_names: Vec<String> = req
+        .features
+        .keys()
+        .map(|name| format!("{}.{name}", group.name))
+        .collect();
+    let features = store.list_feature(&full_names).await?;
+
+    let mut names = Vec::with_capacity(req.features.len());
+    let mut values = Vec::with_capacity(req.features.len());
+    for (name, value) in req.features {
+        let feature = features.iter().find(|f| f.name == name).ok_or_else(|| {
+            Error::DataNotFound(format!(
+                "feature '{name}' not found in group '{}'",
+                group.name
+            ))
+        })?;
+        let value = FeatureValueSeed(feature)
+            .deserialize(value)
+            .map_err(|e| Error::InvalidValue(e.to_string()))?;
+        names.push(name);
+        values.push(value);
+    }
+
+    store
+        .push(PushOpt {
+            entity_key: req.entity_key,
+            group_name: group.name,
+            features: names,
+            values,
+            unix_milli: req.unix_milli,
+        })
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_entities(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult<Json<Vec<Entity>>> {
+    let names = query.names();
+    let entities = store.list_entity(list_opt(&names)).await?;
+    Ok(Json(entities))
+}
+
+async fn list_groups(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult<Json<Vec<Group>>> {
+    let names = query.names();
+    let groups = store.list_group(list_opt(&names)).await?;
+    Ok(Json(groups))
+}
+
+/// Lists features by full names, e.g. `device.model`.
+async fn list_features(
+    State(store): State<Arc<Store>>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult<Json<Vec<RichFeature>>> {
+    let features = store.list_rich_feature(&query.names()).await?;
+    Ok(Json(features))
+}
+
+fn list_opt(names: &Vec<String>) -> ListOpt<'_> {
+    if names.is_empty() {
+        ListOpt::All
+    } else {
+        ListOpt::from(names)
+    }
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use clap::Args;
+
+use feastore::Store;
+
+#[derive(Debug, Args)]
+pub struct SnapshotCmd {
+    /// group name
+    #[arg(short, long)]
+    group: String,
+}
+
+impl SnapshotCmd {
+    pub async fn run(self, store: Store) -> Result<()> {
+        let results = store.snapshot(&self.group).await?;
+        if results.is_empty() {
+            println!("nothing new to snapshot in group '{}'", self.group);
+        }
+        for res in results {
+            println!(
+                "snapshot {} rows at revision {} (id {})",
+                res.rows, res.revision, res.revision_id
+            );
+        }
+        Ok(())
+    }
+}
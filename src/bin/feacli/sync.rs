@@ -0,0 +1,27 @@
+use anyhow::Result;
+use clap::Args;
+
+use feastore::Store;
+
+#[derive(Debug, Args)]
+pub struct SyncCmd {
+    /// group name
+    #[arg(short, long)]
+    group: String,
+
+    /// revision to sync, defaults to the latest one
+    #[arg(short, long)]
+    revision: Option<i64>,
+}
+
+impl SyncCmd {
+    pub async fn run(self, store: Store) -> Result<()> {
+        let res = store.sync(&self.group, self.revision).await?;
+
+        println!(
+            "synced {} rows of revision {} (id {}) into the online store",
+            res.rows, res.revision, res.revision_id
+        );
+        Ok(())
+    }
+}
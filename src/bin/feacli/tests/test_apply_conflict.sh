@@ -0,0 +1,53 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+
+manifest=/tmp/feastore_manifest.yaml
+cat <<EOF > "$manifest"
+kind: Entity
+name: user
+description: 'a user'
+groups:
+- name: student
+  category: stream
+  description: student
+  features:
+  - name: age
+    value-type: string
+    description: 'age'
+- name: user-click
+  category: stream
+  snapshot-interval: 60
+  description: user click post feature
+EOF
+
+case='feacli apply: conflicts fail the apply'
+actual=$(feacli apply -f "$manifest" 2>&1 && echo "exit 0" || echo "exit $?")
+assert_contain "$actual" "apply conflicts with the registered metadata:" "$case" && info "case - $case"
+assert_contain "$actual" "group 'student': category can't be changed from batch to stream" "$case" && info "case - $case"
+assert_contain "$actual" "feature 'student.age': value-type can't be changed from int64 to string" "$case" && info "case - $case"
+assert_contain "$actual" "exit 1" "$case" && info "case - $case"
+
+case='feacli apply: nothing is applied with conflicts'
+expected='user,user'
+actual=$(feacli get entity -o csv | grep '^2,' | cut -d ',' -f 2,3)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply: snapshot interval is changed'
+cat <<EOF > "$manifest"
+kind: Group
+name: user-click
+entity: user
+category: stream
+snapshot-interval: 60
+description: user click post feature
+EOF
+feacli apply -f "$manifest"
+expected='user-click,user,stream,60'
+actual=$(feacli get group -o csv | grep ',user-click,' | cut -d ',' -f 2-5)
+assert_eq "$expected" "$actual" "$case"
@@ -0,0 +1,65 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+
+registry=/tmp/feastore_registry
+rm -rf "$registry" && mkdir -p "$registry/team"
+cat <<EOF > "$registry/entities.yaml"
+kind: Entity
+name: device
+description: 'device'
+EOF
+cat <<EOF > "$registry/team/phone.yaml"
+kind: Group
+name: phone
+entity: device
+category: batch
+description: 'phone'
+features:
+- name: model
+  value-type: string
+  description: 'model'
+EOF
+echo 'not a manifest' > "$registry/README.md"
+
+case='feacli apply: a glob reads the matched files'
+expected="+ entity 'device'
++ group 'phone'
++ feature 'phone.model'
+Plan: 3 to create, 0 to update, 0 to delete, 0 in conflict."
+actual=$(feacli apply -f "$registry/team/*.yaml" -f "$registry/entities.yaml" --dry-run)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply: a glob matching nothing fails'
+actual=$(feacli apply -f "$registry/**/*.json" 2>&1 || true)
+assert_contain "$actual" "no manifest file found" "$case" && info "case - $case"
+
+case='feacli apply: a directory is read recursively, across-file references work'
+feacli apply -f "$registry"
+expected='name,entity,category
+phone,device,batch'
+actual=$(feacli get group -o csv | cut -d ',' -f 2-4)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply: an object declared in two files fails'
+cat <<EOF > "$registry/team/user.yaml"
+kind: Entity
+name: user
+description: 'user'
+---
+kind: Entity
+name: device
+description: 'device'
+EOF
+actual=$(feacli apply -f "$registry" 2>&1 && echo "exit 0" || echo "exit $?")
+assert_contain "$actual" "$registry/team/user.yaml: document 2, line 5, column 1: entity 'device' is already declared in document 1 of $registry/entities.yaml" "$case" && info "case - $case"
+assert_contain "$actual" "exit 1" "$case" && info "case - $case"
+
+case='feacli apply: nothing is applied when a file fails'
+expected='device'
+actual=$(feacli get entity -o csv | tail -n +2 | cut -d ',' -f 2)
+assert_eq "$expected" "$actual" "$case"
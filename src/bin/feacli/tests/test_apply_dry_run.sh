@@ -0,0 +1,54 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+
+manifest=/tmp/feastore_manifest.yaml
+cat <<EOF > "$manifest"
+kind: Entity
+name: device
+description: 'a device'
+groups:
+- name: phone
+  category: stream
+  description: phone
+  features:
+  - name: model
+    value-type: string
+    description: 'model'
+  - name: color
+    value-type: string
+    description: 'color'
+EOF
+
+case='feacli apply --dry-run prints the plan'
+expected="+ feature 'phone.color'
+~ entity 'device': description 'device' -> 'a device'
+! group 'phone': category can't be changed from batch to stream
+Plan: 1 to create, 1 to update, 0 to delete, 1 in conflict."
+actual=$(feacli apply -f "$manifest" --dry-run)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --dry-run changes nothing'
+expected='name,description
+device,device
+user,user'
+actual=$(feacli get entity -o csv | cut -d ',' -f 2,3)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --dry-run --prune'
+actual=$(feacli apply -f "$manifest" --dry-run --prune)
+assert_contain "$actual" "- group 'student'" "$case" && info "case - $case"
+assert_contain "$actual" "- feature 'phone.price'" "$case" && info "case - $case"
+assert_contain "$actual" "Plan: 1 to create, 1 to update, 9 to delete, 1 in conflict." "$case" && info "case - $case"
+
+case='feacli apply --dry-run: no changes after apply'
+sed -i 's/category: stream/category: batch/' "$manifest"
+feacli apply -f "$manifest"
+expected='No changes.'
+actual=$(feacli apply -f "$manifest" --dry-run)
+assert_eq "$expected" "$actual" "$case"
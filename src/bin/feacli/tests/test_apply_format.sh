@@ -0,0 +1,70 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+
+registry=/tmp/feastore_registry
+rm -rf "$registry" && mkdir -p "$registry"
+cat <<EOF > "$registry/entities.json"
+{"kind": "Entity", "name": "device", "description": "device"}
+EOF
+cat <<EOF > "$registry/phone.toml"
+kind = "Group"
+name = "phone"
+entity = "device"
+category = "batch"
+description = "phone"
+
+[[features]]
+name = "model"
+value-type = "string"
+description = "model"
+EOF
+cat <<EOF > "$registry/price.yaml"
+kind: Feature
+name: price
+group: phone
+value-type: int64
+description: 'price'
+EOF
+
+case='feacli apply: json, toml and yaml manifests make up one manifest'
+feacli apply -f "$registry"
+expected='name,group,category,value-type
+model,phone,batch,string
+price,phone,batch,int64'
+actual=$(feacli get feature -o csv | cut -d ',' -f 2-5)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --format overrides the extension'
+manifest=/tmp/feastore_manifest.txt
+echo '{"kind": "Entity", "name": "user", "description": "user"}' > "$manifest"
+feacli apply -f "$manifest" --format json
+expected='device
+user'
+actual=$(feacli get entity -o csv | tail -n +2 | cut -d ',' -f 2)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --format reads stdin'
+echo '{"kind": "Entity", "name": "shop", "description": "shop"}' | feacli apply -f - --format json
+expected='device
+shop
+user'
+actual=$(feacli get entity -o csv | tail -n +2 | cut -d ',' -f 2 | sort)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --format leaves a directory to the extensions'
+feacli apply -f "$registry" --format json
+expected='name,group,category,value-type
+model,phone,batch,string
+price,phone,batch,int64'
+actual=$(feacli get feature -o csv | cut -d ',' -f 2-5)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply: a toml syntax error is located'
+printf 'kind = "Entity"\nname = user\n' > "$registry/user.toml"
+actual=$(feacli apply -f "$registry/user.toml" 2>&1 || true)
+assert_contain "$actual" "$registry/user.toml: document 1, line 2, column 8:" "$case" && info "case - $case"
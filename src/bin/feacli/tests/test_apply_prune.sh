@@ -0,0 +1,67 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+import_device_sample 1000 >/dev/null
+feacli sync --group phone >/dev/null
+
+manifest=/tmp/feastore_manifest.yaml
+cat <<EOF > "$manifest"
+kind: Entity
+name: device
+description: 'device'
+groups:
+- name: phone
+  category: batch
+  description: phone
+  features:
+  - name: model
+    value-type: string
+    description: 'model'
+EOF
+
+case='feacli apply --prune: aborted without confirmation'
+actual=$(echo n | feacli apply -f "$manifest" --prune 2>&1 || true)
+assert_contain "$actual" "- entity 'user'" "$case" && info "case - $case"
+assert_contain "$actual" "- feature 'phone.price'" "$case" && info "case - $case"
+assert_contain "$actual" "apply aborted." "$case" && info "case - $case"
+actual=$(feacli get entity -o csv | cut -d ',' -f 2)
+assert_eq "$(printf 'name\ndevice\nuser')" "$actual" "$case"
+
+case='feacli apply --prune works'
+expected="deleted entity 'user'
+deleted group 'student'
+deleted group 'user-click'
+deleted feature 'phone.price'
+deleted feature 'student.name'
+deleted feature 'student.gender'
+deleted feature 'student.age'
+deleted feature 'user-click.last_5_click_posts'
+deleted feature 'user-click.number_of_user_starred_posts'"
+actual=$(feacli apply -f "$manifest" --prune --yes)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --prune: the metadata follows the manifest'
+expected='id,name,group,category,value-type,description
+2,model,phone,batch,string,model'
+actual=$(feacli get feature -o csv | cut -d "," -f 1-6)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --prune: the data of pruned features is dropped'
+expected='device,model
+1,xiaomi-mix3'
+actual=$(feacli export --group phone | head -n 2)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --prune: nothing to prune'
+expected=''
+actual=$(feacli apply -f "$manifest" --prune </dev/null)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli apply --yes requires --prune'
+actual=$(feacli apply -f "$manifest" --yes 2>&1 || true)
+assert_contain "$actual" "--prune" "$case" && info "case - $case"
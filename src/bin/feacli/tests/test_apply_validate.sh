@@ -0,0 +1,50 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+
+manifest=/tmp/feastore_manifest.yaml
+cat <<EOF > "$manifest"
+kind: Entity
+name: device
+description: 'device'
+---
+items:
+- kind: Group
+  name: phone
+  entity: device
+  category: batch
+  description: 'phone'
+  features:
+  - name: model
+    value-type: strin
+    description: 'model'
+- kind: Feature
+  name: price
+  group: phone
+  value-type: int64
+  descripton: 'price'
+- kind: Feature
+  name: color
+  group: phone
+  value-type: string
+- kind: Entit
+  name: user
+EOF
+
+case='feacli apply: invalid manifest reports the problem of every object located'
+actual=$(feacli apply -f "$manifest" 2>&1 && echo "exit 0" || echo "exit $?")
+assert_contain "$actual" "invalid manifest:" "$case" && info "case - $case"
+assert_contain "$actual" "document 2, line 13, column 5: items\[0\].features\[0\].value-type: unknown variant \`strin\`" "$case" && info "case - $case"
+assert_contain "$actual" "document 2, line 19, column 3: items\[1\].descripton: unknown field \`descripton\`" "$case" && info "case - $case"
+assert_contain "$actual" "document 2, line 20, column 3: items\[2\]: missing field \`description\`" "$case" && info "case - $case"
+assert_contain "$actual" "document 2, line 24, column 3: items\[3\].kind: invalid kind 'Entit'" "$case" && info "case - $case"
+assert_contain "$actual" "exit 1" "$case" && info "case - $case"
+
+case='feacli apply: nothing is applied with an invalid manifest'
+expected=''
+actual=$(feacli get entity -o csv)
+assert_eq "$expected" "$actual" "$case"
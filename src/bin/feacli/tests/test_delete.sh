@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+import_device_sample 1000 >/dev/null
+feacli sync --group phone >/dev/null
+
+case='feacli delete feature works'
+expected="deleted feature 'phone.price'"
+actual=$(feacli delete feature phone.price)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli delete feature: the offline values are dropped'
+expected='device,model
+1,xiaomi-mix3'
+actual=$(feacli export --group phone | head -n 2)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli delete feature: the online values are dropped'
+expected='entity_key,phone.model
+1,xiaomi-mix3'
+actual=$(feacli get online --entity-key 1 --features phone.model -o csv)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli delete group: refused with features'
+actual=$(feacli delete group phone 2>&1 || true)
+assert_contain "$actual" "group 'phone' still has features: model" "$case" && info "case - $case"
+
+case='feacli delete group --cascade works'
+expected="deleted group 'phone'"
+actual=$(feacli delete group phone --cascade)
+assert_eq "$expected" "$actual" "$case"
+expected='name
+student
+user-click'
+actual=$(feacli get group -o csv | cut -d ',' -f 2)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli delete entity: refused with groups'
+actual=$(feacli delete entity user 2>&1 || true)
+assert_contain "$actual" "entity 'user' still has groups: student, user-click" "$case" && info "case - $case"
+
+case='feacli delete entity --cascade works'
+expected="deleted entity 'user'"
+actual=$(feacli delete entity user --cascade)
+assert_eq "$expected" "$actual" "$case"
+expected='name
+device'
+actual=$(feacli get entity -o csv | cut -d ',' -f 2)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli delete: not found'
+actual=$(feacli delete entity user 2>&1 || true)
+assert_contain "$actual" "entity 'user' not found" "$case" && info "case - $case"
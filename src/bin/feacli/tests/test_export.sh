@@ -0,0 +1,61 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+
+file=/tmp/feastore_device.csv
+cat <<EOF > "$file"
+device,price
+2,5299
+1,3999
+EOF
+feacli import --group phone --input-file "$file" --revision 1000 >/dev/null
+
+cat <<EOF > "$file"
+device,model,price
+1,xiaomi-mix3,2999
+EOF
+feacli import --group phone --input-file "$file" --revision 2000 >/dev/null
+
+case='feacli export works'
+expected='device,model,price
+1,,3999
+2,,5299'
+actual=$(feacli export --group phone --unix-milli 1999)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli export: the latest revision'
+expected='device,model
+1,xiaomi-mix3'
+actual=$(feacli export --group phone --features model)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli export --output-file'
+output=/tmp/feastore_exported.csv
+feacli export --group phone --unix-milli 2000 --output-file "$output"
+expected='device,model,price
+1,xiaomi-mix3,2999'
+assert_eq "$expected" "$(cat "$output")" "$case"
+
+case='feacli export: no revision by then'
+actual=$(feacli export --group phone --unix-milli 999 2>&1 || true)
+assert_contain "$actual" "group 'phone' has no revision at 999" "$case" && info "case - $case"
+
+case='feacli export: unknown feature'
+actual=$(feacli export --group phone --features color 2>&1 || true)
+assert_contain "$actual" "feature 'color' not found in group 'phone'" "$case" && info "case - $case"
+
+case='feacli export: a null string is imported back as an empty string'
+feacli export --group phone --unix-milli 1999 --output-file "$output"
+feacli import --group phone --input-file "$output" --revision 3000 >/dev/null
+feacli sync --group phone >/dev/null
+expected='
+entity_key: '"'"'1'"'"'
+phone.model: '"''"'
+phone.price: 3999'
+actual=$(feacli get online --entity-key 1 --features phone.model,phone.price -o yaml)
+assert_eq "$expected" "$actual" "$case"
@@ -0,0 +1,29 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+import_device_sample 1000 >/dev/null
+feacli sync --group phone >/dev/null
+
+case='feacli get online works'
+expected='entity_key,phone.model,phone.price
+1,xiaomi-mix3,3999
+6,,
+4,apple-iphone11,4999'
+actual=$(feacli get online --entity-key 1,6,4 --features phone.model,phone.price -o csv)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli get online -o yaml'
+expected='
+entity_key: '"'"'1'"'"'
+phone.price: 3999'
+actual=$(feacli get online --entity-key 1 --features phone.price -o yaml)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli get online: unknown feature'
+actual=$(feacli get online --entity-key 1 --features phone.color -o csv 2>&1 || true)
+assert_contain "$actual" "feature 'phone.color' not found" "$case" && info "case - $case"
@@ -0,0 +1,60 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+
+case='feacli import works'
+expected='imported 5 rows into revision 1000 (id 1)'
+actual=$(import_device_sample 1000)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli import: the same revision cannot be imported twice'
+actual=$(import_device_sample 1000 2>&1 || true)
+assert_contain "$actual" "revision 1000 of group 'phone' already exists" "$case" && info "case - $case"
+
+case='feacli import with a delimiter'
+file=/tmp/feastore_student.csv
+cat <<EOF > "$file"
+user|name|age
+1|lihua|20
+2||
+EOF
+expected='imported 2 rows into revision 2000 (id 2)'
+actual=$(feacli import --group student --input-file "$file" --revision 2000 --delimiter '|')
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli import: the first column must be the entity key'
+cat <<EOF > "$file"
+name,age
+lihua,20
+EOF
+actual=$(feacli import --group student --input-file "$file" 2>&1 || true)
+assert_contain "$actual" "the first column of the header must be the entity key 'user'" "$case" && info "case - $case"
+
+case='feacli import: unknown feature'
+cat <<EOF > "$file"
+user,name,height
+1,lihua,180
+EOF
+actual=$(feacli import --group student --input-file "$file" 2>&1 || true)
+assert_contain "$actual" "feature 'student.height' not found" "$case" && info "case - $case"
+
+case='feacli import: invalid value'
+cat <<EOF > "$file"
+user,name,age
+1,lihua,twenty
+EOF
+actual=$(feacli import --group student --input-file "$file" 2>&1 || true)
+assert_contain "$actual" "line 2: invalid value 'twenty' of feature 'student.age'" "$case" && info "case - $case"
+
+case='feacli import: stream group'
+cat <<EOF > "$file"
+user,number_of_user_starred_posts
+1,10
+EOF
+actual=$(feacli import --group user-click --input-file "$file" 2>&1 || true)
+assert_contain "$actual" "group 'user-click' is not a batch group" "$case" && info "case - $case"
@@ -0,0 +1,73 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+
+file=/tmp/feastore_device.csv
+cat <<EOF > "$file"
+device,price
+1,3999
+2,5299
+EOF
+feacli import --group phone --input-file "$file" --revision 1000 >/dev/null
+
+cat <<EOF > "$file"
+device,model,price
+1,xiaomi-mix3,2999
+3,oneplus-8,3999
+EOF
+feacli import --group phone --input-file "$file" --revision 2000 >/dev/null
+
+entity_rows=/tmp/feastore_entity_rows.csv
+cat <<EOF > "$entity_rows"
+device,unix_milli,label
+1,500,a
+1,1000,b
+2,1999,c
+1,2000,d
+2,2500,e
+EOF
+
+case='feacli join works'
+expected='entity_key,unix_milli,label,phone.model,phone.price
+1,500,a,,
+1,1000,b,,3999
+2,1999,c,,5299
+1,2000,d,xiaomi-mix3,2999
+2,2500,e,,'
+actual=$(feacli join --entity-rows "$entity_rows" --features phone.model,phone.price)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli join --output-file'
+output=/tmp/feastore_joined.csv
+feacli join --entity-rows "$entity_rows" --features phone.price --output-file "$output"
+expected='entity_key,unix_milli,label,phone.price
+1,500,a,
+1,1000,b,3999
+2,1999,c,5299
+1,2000,d,2999
+2,2500,e,'
+assert_eq "$expected" "$(cat "$output")" "$case"
+
+case='feacli join --output parquet'
+output=/tmp/feastore_joined.parquet
+rm -f "$output"
+feacli join --entity-rows "$entity_rows" --features phone.price --output parquet --output-file "$output"
+actual=$(head -c 4 "$output")
+assert_eq "PAR1" "$actual" "$case"
+
+case='feacli join: unknown feature'
+actual=$(feacli join --entity-rows "$entity_rows" --features phone.price,phone.color 2>&1 || true)
+assert_contain "$actual" "feature 'phone.color' not found" "$case" && info "case - $case"
+
+case='feacli join: invalid unix milliseconds'
+cat <<EOF > "$entity_rows"
+device,unix_milli
+1,yesterday
+EOF
+actual=$(feacli join --entity-rows "$entity_rows" --features phone.price 2>&1 || true)
+assert_contain "$actual" "invalid unix milliseconds 'yesterday' at line 2" "$case" && info "case - $case"
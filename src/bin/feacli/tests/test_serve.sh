@@ -0,0 +1,78 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+import_device_sample 1000 >/dev/null
+feacli sync --group phone >/dev/null
+
+addr=127.0.0.1:18080
+feacli serve --http-addr "$addr" >/dev/null &
+pid=$!
+trap 'kill "$pid" 2>/dev/null || true' EXIT
+
+for _ in $(seq 50); do
+  curl -s "http://$addr/entities" >/dev/null && break
+  sleep 0.1
+done
+
+request() {
+  local method="$1" path="$2" body="${3:-}"
+  curl -s -X "$method" -H 'Content-Type: application/json' -d "$body" -w ' %{http_code}' "http://$addr$path"
+}
+
+case='serve: online get'
+expected='{"entity_key":"1","features":["phone.model","phone.price"],"values":["xiaomi-mix3",3999]} 200'
+actual=$(request POST /online/get '{"entity_key":"1","features":["phone.model","phone.price"]}')
+assert_eq "$expected" "$actual" "$case"
+
+case='serve: online multi-get'
+expected='[{"entity_key":"4","features":["phone.price"],"values":[4999]},{"entity_key":"6","features":["phone.price"],"values":[null]}] 200'
+actual=$(request POST /online/multi-get '{"entity_keys":["4","6"],"features":["phone.price"]}')
+assert_eq "$expected" "$actual" "$case"
+
+case='serve: online multi-get a repeated key'
+expected='[{"entity_key":"4","features":["phone.price"],"values":[4999]},{"entity_key":"6","features":["phone.price"],"values":[null]},{"entity_key":"4","features":["phone.price"],"values":[4999]}] 200'
+actual=$(request POST /online/multi-get '{"entity_keys":["4","6","4"],"features":["phone.price"]}')
+assert_eq "$expected" "$actual" "$case"
+
+case='serve: push'
+expected=' 204'
+actual=$(request POST /push '{"group":"user-click","entity_key":"u1","features":{"number_of_user_starred_posts":3,"last_5_click_posts":"1,2"},"unix_milli":2000}')
+assert_eq "$expected" "$actual" "$case"
+request POST /push '{"group":"user-click","entity_key":"u1","features":{"number_of_user_starred_posts":1,"last_5_click_posts":null},"unix_milli":1000}' >/dev/null
+
+case='serve: pushed values are the latest by event time'
+expected='{"entity_key":"u1","features":["user-click.number_of_user_starred_posts"],"values":[3]} 200'
+actual=$(request POST /online/get '{"entity_key":"u1","features":["user-click.number_of_user_starred_posts"]}')
+assert_eq "$expected" "$actual" "$case"
+
+case='serve: push an invalid value'
+actual=$(request POST /push '{"group":"user-click","entity_key":"u1","features":{"number_of_user_starred_posts":"many"}}')
+assert_contain "$actual" "invalid value 'many' of feature 'user-click.number_of_user_starred_posts'" "$case" && info "case - $case"
+assert_contain "$actual" " 400" "$case" && info "case - $case"
+
+case='serve: push some features of a group'
+actual=$(request POST /push '{"group":"user-click","entity_key":"u1","features":{"last_5_click_posts":"3"},"unix_milli":3000}')
+assert_eq "{\"error\":\"features 'number_of_user_starred_posts' of group 'user-click' are not pushed, every feature is pushed at once\"} 400" "$actual" "$case"
+
+case='serve: push to a batch group'
+actual=$(request POST /push '{"group":"phone","entity_key":"1","features":{"price":1}}')
+assert_eq "{\"error\":\"group 'phone' is not a stream group\"} 400" "$actual" "$case"
+
+case='serve: unknown feature'
+actual=$(request POST /online/get '{"entity_key":"1","features":["phone.color"]}')
+assert_eq "{\"error\":\"feature 'phone.color' not found\"} 404" "$actual" "$case"
+
+case='serve: list groups'
+actual=$(curl -s "http://$addr/groups?names=phone")
+assert_contain "$actual" '"name":"phone"' "$case" && info "case - $case"
+assert_not_contain "$actual" '"name":"student"' "$case" && info "case - $case"
+
+case='serve: list features'
+actual=$(curl -s "http://$addr/features?names=phone.price")
+expected='[{"kind":"Feature","name":"price","group":"phone","value-type":"int64","description":"price"}]'
+assert_eq "$expected" "$actual" "$case"
@@ -0,0 +1,73 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+
+case='feacli snapshot: nothing pushed'
+expected="nothing new to snapshot in group 'user-click'"
+actual=$(feacli snapshot --group user-click)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli snapshot: batch group'
+actual=$(feacli snapshot --group phone 2>&1 || true)
+assert_contain "$actual" "group 'phone' is not a stream group" "$case" && info "case - $case"
+
+case='feacli snapshot: group not found'
+actual=$(feacli snapshot --group not-exist 2>&1 || true)
+assert_contain "$actual" "group 'not-exist' not found" "$case" && info "case - $case"
+
+addr=127.0.0.1:18081
+feacli serve --http-addr "$addr" >/dev/null &
+pid=$!
+trap 'kill "$pid" 2>/dev/null || true' EXIT
+
+for _ in $(seq 50); do
+  curl -s "http://$addr/entities" >/dev/null && break
+  sleep 0.1
+done
+
+push() {
+  local key="$1" posts="$2" starred="$3" unix_milli="$4"
+  curl -s -X POST -H 'Content-Type: application/json' \
+    -d "{\"group\":\"user-click\",\"entity_key\":\"$key\",\"features\":{\"last_5_click_posts\":\"$posts\",\"number_of_user_starred_posts\":$starred},\"unix_milli\":$unix_milli}" \
+    "http://$addr/push"
+}
+
+push u1 1 1 1500
+push u2 2 2 1800
+push u1 3 3 3200
+
+case='feacli snapshot works'
+expected='snapshot 2 rows at revision 2000 (id 1)'
+actual=$(feacli snapshot --group user-click)
+assert_eq "$expected" "$actual" "$case"
+
+# pushed after the snapshot was taken, but its event time is before it
+push u2 4 4 1900
+
+entity_rows=/tmp/feastore_entity_rows.csv
+cat <<EOF > "$entity_rows"
+user,unix_milli
+u1,1000
+u2,2500
+u1,3500
+EOF
+
+case='feacli join across a snapshot, with a late event'
+expected='entity_key,unix_milli,user-click.last_5_click_posts,user-click.number_of_user_starred_posts
+u1,1000,,
+u2,2500,4,4
+u1,3500,3,3'
+actual=$(feacli join --entity-rows "$entity_rows" --features user-click.last_5_click_posts,user-click.number_of_user_starred_posts)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli export across a snapshot, with a late event'
+expected='user,last_5_click_posts,number_of_user_starred_posts
+u1,1,1
+u2,4,4'
+actual=$(feacli export --group user-click --unix-milli 2500)
+assert_eq "$expected" "$actual" "$case"
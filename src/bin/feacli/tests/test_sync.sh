@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+source ./utils.sh
+source ./assert.sh
+
+init_store
+register_features
+
+case='feacli sync: no revision'
+actual=$(feacli sync --group phone 2>&1 || true)
+assert_contain "$actual" "group 'phone' has no revision" "$case" && info "case - $case"
+
+import_device_sample 1000 >/dev/null
+import_device_sample 2000 >/dev/null
+
+case='feacli sync works'
+expected='synced 5 rows of revision 2000 (id 2) into the online store'
+actual=$(feacli sync --group phone)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli sync records the online revision'
+actual=$(feacli get group -n phone -o csv | cut -d ',' -f 7 | tail -n +2)
+assert_eq "2" "$actual" "$case"
+
+case='feacli sync a given revision'
+expected='synced 5 rows of revision 1000 (id 1) into the online store'
+actual=$(feacli sync --group phone --revision 1000)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli sync is safe to re-run'
+actual=$(feacli sync --group phone --revision 1000)
+assert_eq "$expected" "$actual" "$case"
+
+case='feacli sync: revision not found'
+actual=$(feacli sync --group phone --revision 3000 2>&1 || true)
+assert_contain "$actual" "revision 3000 of group 'phone' not found" "$case" && info "case - $case"
+
+case='feacli sync: stream group'
+actual=$(feacli sync --group user-click 2>&1 || true)
+assert_contain "$actual" "group 'user-click' is not a batch group" "$case" && info "case - $case"
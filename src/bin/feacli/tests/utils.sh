@@ -6,9 +6,10 @@ alias feacli="../../../../target/debug/feacli --config config.yaml"
 
 function init_store {
   echo "init_store" >> /dev/null
-  db_file="/tmp/feastore.db"
-  [ -f "$db_file" ] && rm "$db_file"
-  touch "$db_file"
+  for db_file in /tmp/feastore.db /tmp/feastore_online.db /tmp/feastore_offline.db; do
+    [ -f "$db_file" ] && rm "$db_file"
+    touch "$db_file"
+  done
 }
 
 # register features for the sample data
@@ -41,3 +42,24 @@ register_features() {
       --value-type "int64"  \
       --description "number of posts that users starred today"
 }
+
+# import sample data of the phone group
+import_device_sample() {
+    local revision="${1:-}"
+    local file=/tmp/feastore_device.csv
+
+    cat <<EOF > "$file"
+device,model,price
+1,xiaomi-mix3,3999
+2,huawei-p40,5299
+3,oneplus-8,3999
+4,apple-iphone11,4999
+5,apple-iphone12,6999
+EOF
+
+    if [ -n "$revision" ]; then
+        feacli import --group phone --input-file "$file" --revision "$revision" --description "test data"
+    else
+        feacli import --group phone --input-file "$file" --description "test data"
+    fi
+}
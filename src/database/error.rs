@@ -8,11 +8,22 @@ pub enum Error {
     #[error("{0} not found by id {1}")]
     ColumnNotFound(String, String),
 
+    #[error("invalid value '{value}' of feature '{feature}': {reason}")]
+    InvalidValue {
+        feature: String,
+        value: String,
+        reason: String,
+    },
+
     #[error("{0}")]
     SqlxError(String),
 
     #[error("{0}")]
     Other(String),
+
+    /// A failure of applying an object, e.g. `group 'device'`.
+    #[error("apply {object} failed: {source}")]
+    Apply { object: String, source: Box<Error> },
 }
 
 impl From<String> for Error {
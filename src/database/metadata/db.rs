@@ -1,8 +1,9 @@
 use crate::database::metadata::{
-    sqlite, CreateFeatureOpt, CreateGroupOpt, Entity, GetOpt, Group, ListOpt, RichEntity, RichGroup,
+    sqlite, CreateFeatureOpt, CreateGroupOpt, CreateRevisionOpt, Entity, GetOpt, GetRevisionOpt,
+    Group, ListOpt, ListRevisionOpt, Revision, RichEntity, RichGroup,
 };
 use crate::database::Result;
-use crate::feastore::apply;
+use crate::feastore::ApplyPlan;
 use crate::feastore::BackendOpt;
 
 use super::types::{Feature, ListFeatureOpt};
@@ -71,6 +72,12 @@ impl DataStore {
         }
     }
 
+    pub(crate) async fn update_online_revision(&self, id: i64, revision_id: i64) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.update_online_revision(id, revision_id).await,
+        }
+    }
+
     pub(crate) async fn get_group<'a>(&self, opt: GetOpt<'a>) -> Result<Option<Group>> {
         match self {
             Self::Sqlite(db) => db.get_group(opt).await,
@@ -95,15 +102,33 @@ impl DataStore {
         }
     }
 
+    pub(crate) async fn delete_entity(&self, id: i64, cascade: bool) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.delete_entity(id, cascade).await,
+        }
+    }
+
+    pub(crate) async fn delete_group(&self, id: i64, cascade: bool) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.delete_group(id, cascade).await,
+        }
+    }
+
+    pub(crate) async fn delete_feature(&self, id: i64) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.delete_feature(id).await,
+        }
+    }
+
     pub(crate) async fn get_feature<'a>(&self, opt: GetOpt<'a>) -> Result<Option<Feature>> {
         match self {
             Self::Sqlite(db) => db.get_feature(opt).await,
         }
     }
 
-    pub(crate) async fn apply(&self, stage: apply::ApplyStage) -> Result<()> {
+    pub(crate) async fn apply(&self, plan: &ApplyPlan) -> Result<()> {
         match self {
-            Self::Sqlite(db) => db.apply(stage).await,
+            Self::Sqlite(db) => db.apply(plan).await,
         }
     }
 
@@ -118,4 +143,22 @@ impl DataStore {
             Self::Sqlite(db) => db.list_feature(opt).await,
         }
     }
+
+    pub(crate) async fn create_revision(&self, revision: CreateRevisionOpt) -> Result<i64> {
+        match self {
+            Self::Sqlite(db) => db.create_revision(revision).await,
+        }
+    }
+
+    pub(crate) async fn get_revision(&self, opt: GetRevisionOpt) -> Result<Option<Revision>> {
+        match self {
+            Self::Sqlite(db) => db.get_revision(opt).await,
+        }
+    }
+
+    pub(crate) async fn list_revision(&self, opt: ListRevisionOpt) -> Result<Vec<Revision>> {
+        match self {
+            Self::Sqlite(db) => db.list_revision(opt).await,
+        }
+    }
 }
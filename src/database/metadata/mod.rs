@@ -17,7 +17,15 @@ pub use types::Feature;
 pub use types::RichFeature;
 pub use types::ValueType;
 
+pub use types::FeatureValue;
+pub use types::FeatureValueSeed;
+
+pub use types::CreateRevisionOpt;
+pub use types::Revision;
+
 pub use types::GetOpt;
+pub use types::GetRevisionOpt;
 pub use types::ListFeatureOpt;
 pub use types::ListGroupOpt;
 pub use types::ListOpt;
+pub use types::ListRevisionOpt;
@@ -1,13 +1,15 @@
-use sqlx::{FromRow, Sqlite, SqlitePool, Transaction};
+use itertools::Itertools;
+use sqlx::{FromRow, Sqlite, SqliteConnection, SqlitePool, Transaction};
 
 use crate::database::metadata::sqlite::schema;
 use crate::database::metadata::types::{Feature, Group2, ListFeatureOpt, ListGroupOpt};
 use crate::database::metadata::{
-    CreateFeatureOpt, CreateGroupOpt, Entity, GetOpt, Group, ListOpt, RichEntity, RichFeature,
-    RichGroup,
+    CreateFeatureOpt, CreateGroupOpt, CreateRevisionOpt, Entity, GetOpt, GetRevisionOpt, Group,
+    ListOpt, ListRevisionOpt, Revision, RichEntity, RichFeature, RichGroup,
 };
 use crate::database::{Error, Result, SQLiteOpt};
-use crate::feastore::apply::ApplyStage;
+use crate::feastore::plan;
+use crate::feastore::{ApplyPlan, Change, Prune};
 
 pub struct DB {
     pool: SqlitePool,
@@ -30,39 +32,74 @@ impl DB {
 
     async fn create_schemas(&self) {
         schema::create_tables(&self.pool).await;
+        schema::add_columns(&self.pool).await;
         schema::create_views(&self.pool).await;
         schema::create_trigger(&self.pool).await;
     }
 
-    pub(crate) async fn apply(&self, stage: ApplyStage) -> Result<()> {
+    /// Executes an apply plan in one transaction, which is rolled back on failure.
+    pub(crate) async fn apply(&self, plan: &ApplyPlan) -> Result<()> {
         let mut tx = self.pool.begin().await?;
 
-        match self.apply_internal(&mut tx, stage).await {
+        match self.apply_internal(&mut tx, plan).await {
             Ok(_) => tx.commit().await.map_err(|e| e.into()),
-            Err(_) => tx.rollback().await.map_err(|e| e.into()),
+            Err(e) => {
+                tx.rollback().await?;
+                Err(e)
+            }
         }
     }
 
     async fn apply_internal(
         &self,
         tx: &mut Transaction<'_, Sqlite>,
-        mut stage: ApplyStage,
+        plan: &ApplyPlan,
     ) -> Result<()> {
-        for e in stage.new_entities.drain(..) {
-            self.apply_entity(tx, e).await?;
+        for e in &plan.create_entities {
+            create_entity(&mut **tx, &e.name, &e.description)
+                .await
+                .map_err(applying("entity", &e.name))?;
         }
 
-        for mut g in stage.new_groups.drain(..) {
-            if let Some(name) = g.entity_name.take() {
-                self.apply_group(tx, &name, g).await?;
-            }
+        for g in &plan.create_groups {
+            self.apply_group(tx, g)
+                .await
+                .map_err(applying("group", &g.name))?;
         }
 
-        for f in stage.new_features.drain(..) {
-            self.apply_feature(tx, f).await?;
+        for f in &plan.create_features {
+            let full_name = format!("{}.{}", f.group_name.as_deref().unwrap_or_default(), f.name);
+            self.apply_feature(tx, f)
+                .await
+                .map_err(applying("feature", &full_name))?;
         }
 
-        Ok(())
+        // only descriptions of entities and features can be changed
+        for update in &plan.update_entities {
+            if let Change::Description { ref to, .. } = update.change {
+                update_entity(&mut **tx, update.id, to)
+                    .await
+                    .map_err(applying("entity", &update.name))?;
+            }
+        }
+        for update in &plan.update_groups {
+            match update.change {
+                Change::Description { ref to, .. } => update_group(&mut **tx, update.id, to).await,
+                Change::SnapshotInterval { to, .. } => {
+                    update_snapshot_interval(&mut **tx, update.id, to).await
+                }
+            }
+            .map_err(applying("group", &update.name))?;
+        }
+        for update in &plan.update_features {
+            if let Change::Description { ref to, .. } = update.change {
+                update_feature(&mut **tx, update.id, to)
+                    .await
+                    .map_err(applying("feature", &update.name))?;
+            }
+        }
+
+        prune_internal(tx, &plan.prune).await
     }
 
     pub(crate) async fn list_rich_entity<'a>(&self, opt: ListOpt<'a>) -> Result<Vec<RichEntity>> {
@@ -75,7 +112,7 @@ impl DB {
             let the_groups = groups
                 .iter()
                 .filter(|g| g.entity_name == entity_name)
-                .map(|g| g.clone())
+                .cloned()
                 .collect::<Vec<RichGroup>>();
 
             res.push(RichEntity::from(entity, Some(the_groups)));
@@ -85,9 +122,7 @@ impl DB {
 
     pub(crate) async fn list_rich_group<'a>(&self, opt: ListOpt<'a>) -> Result<Vec<RichGroup>> {
         let groups = self.list_group(opt).await?;
-        let features = self
-            .list_feature(ListFeatureOpt::EntityIDs(vec![1])) // FIXME
-            .await?;
+        let features = self.list_feature(ListFeatureOpt::All).await?;
 
         let mut res = vec![];
         for group in groups {
@@ -103,86 +138,46 @@ impl DB {
         Ok(res)
     }
 
-    async fn apply_entity(
-        &self,
-        tx: &mut Transaction<'_, Sqlite>,
-        entity: RichEntity,
-    ) -> Result<()> {
-        let old_entity = get_entity(&mut *tx, GetOpt::Name(&entity.name)).await?;
-
-        if let Some(oe) = old_entity {
-            if oe.description != entity.description {
-                update_entity(&mut *tx, oe.id, &entity.description).await?;
-            }
-            return Ok(());
-        }
-
-        create_entity(&mut *tx, &entity.name, &entity.description).await?;
-
-        Ok(())
-    }
-
-    async fn apply_group(
-        &self,
-        tx: &mut Transaction<'_, Sqlite>,
-        entity_name: &str,
-        group: RichGroup,
-    ) -> Result<()> {
-        let old_group = get_group(&mut *tx, GetOpt::Name(&group.name)).await?;
-
-        if let Some(og) = old_group {
-            if og.description != group.description {
-                update_group(&mut *tx, og.id, &group.description).await?;
-            }
-            return Ok(());
-        }
-
-        if let Some(e) = get_entity(&mut *tx, GetOpt::Name(entity_name)).await? {
-            create_group(
-                &mut *tx,
-                CreateGroupOpt {
-                    entity_id: e.id,
-                    name: group.name,
-                    category: group.category,
-                    snapshot_interval: group.snapshot_interval,
-                    description: group.description,
-                },
-            )
-            .await?;
-        }
+    async fn apply_group(&self, tx: &mut Transaction<'_, Sqlite>, group: &RichGroup) -> Result<()> {
+        let entity_name = group.entity_name.as_deref().unwrap_or_default();
+        let entity = get_entity(&mut **tx, GetOpt::Name(entity_name))
+            .await?
+            .ok_or_else(|| Error::Other(format!("entity '{entity_name}' not found")))?;
 
+        create_group(
+            &mut **tx,
+            CreateGroupOpt {
+                entity_id: entity.id,
+                name: group.name.clone(),
+                category: group.category.clone(),
+                snapshot_interval: group.snapshot_interval,
+                description: group.description.clone(),
+            },
+        )
+        .await?;
         Ok(())
     }
 
     async fn apply_feature(
         &self,
         tx: &mut Transaction<'_, Sqlite>,
-        feature: RichFeature,
+        feature: &RichFeature,
     ) -> Result<()> {
-        let old_feature = get_feature(&mut *tx, GetOpt::Name(&feature.name)).await?;
-
-        if let Some(of) = old_feature {
-            if of.description != feature.description {
-                update_feature(&mut *tx, of.id, &feature.description).await?;
-            }
-            return Ok(());
-        }
-
-        if let Some(group_name) = feature.group_name {
-            if let Some(g) = get_group(&mut *tx, GetOpt::Name(&group_name)).await? {
-                create_feature(
-                    &mut *tx,
-                    CreateFeatureOpt {
-                        group_id: g.id,
-                        feature_name: feature.name,
-                        description: feature.description,
-                        value_type: feature.value_type,
-                    },
-                )
-                .await?;
-            }
-        }
+        let group_name = feature.group_name.as_deref().unwrap_or_default();
+        let group = get_group(&mut **tx, GetOpt::Name(group_name))
+            .await?
+            .ok_or_else(|| Error::Other(format!("group '{group_name}' not found")))?;
 
+        create_feature(
+            &mut **tx,
+            CreateFeatureOpt {
+                group_id: group.id,
+                feature_name: feature.name.clone(),
+                description: feature.description.clone(),
+                value_type: feature.value_type.clone(),
+            },
+        )
+        .await?;
         Ok(())
     }
 
@@ -232,6 +227,10 @@ impl DB {
         update_group(&self.pool, id, new_description).await
     }
 
+    pub(crate) async fn update_online_revision(&self, id: i64, revision_id: i64) -> Result<()> {
+        update_online_revision(&self.pool, id, revision_id).await
+    }
+
     pub(crate) async fn get_group<'a>(&self, opt: GetOpt<'a>) -> Result<Option<Group>> {
         get_group(&self.pool, opt).await
     }
@@ -251,6 +250,34 @@ impl DB {
     pub(crate) async fn get_feature<'a>(&self, opt: GetOpt<'a>) -> Result<Option<Feature>> {
         get_feature(&self.pool, opt).await
     }
+
+    pub(crate) async fn delete_entity(&self, id: i64, cascade: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        delete_entity(&mut *tx, id, cascade).await?;
+        tx.commit().await.map_err(|e| e.into())
+    }
+
+    pub(crate) async fn delete_group(&self, id: i64, cascade: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        delete_group(&mut *tx, id, cascade).await?;
+        tx.commit().await.map_err(|e| e.into())
+    }
+
+    pub(crate) async fn delete_feature(&self, id: i64) -> Result<()> {
+        delete_feature(&self.pool, id).await
+    }
+
+    pub(crate) async fn create_revision(&self, revision: CreateRevisionOpt) -> Result<i64> {
+        create_revision(&self.pool, revision).await
+    }
+
+    pub(crate) async fn get_revision(&self, opt: GetRevisionOpt) -> Result<Option<Revision>> {
+        get_revision(&self.pool, opt).await
+    }
+
+    pub(crate) async fn list_revision(&self, opt: ListRevisionOpt) -> Result<Vec<Revision>> {
+        list_revision(&self.pool, opt).await
+    }
 }
 
 async fn create_entity<'a, A>(conn: A, name: &str, description: &str) -> Result<i64>
@@ -411,13 +438,59 @@ where
     }
 }
 
+async fn update_snapshot_interval<'a, A>(conn: A, id: i64, interval: Option<i32>) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let rows_affected = sqlx::query("UPDATE feature_group SET snapshot_interval = ? WHERE id = ?")
+        .bind(interval)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    if rows_affected != 1 {
+        Err(Error::ColumnNotFound(
+            "feature_group".to_owned(),
+            id.to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+async fn update_online_revision<'a, A>(conn: A, id: i64, revision_id: i64) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let rows_affected = sqlx::query("UPDATE feature_group SET online_revision_id = ? WHERE id = ?")
+        .bind(revision_id)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    if rows_affected != 1 {
+        Err(Error::ColumnNotFound(
+            "feature_group".to_owned(),
+            id.to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
 async fn get_group<'a, A>(conn: A, opt: GetOpt<'a>) -> Result<Option<Group>>
 where
     A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
 {
     let mut conn = conn.acquire().await?;
     let mut query_str = r#"
-        SELECT g.id, g.name, e.name as entity_name, g.category, g.entity_id, g.snapshot_interval, g.description, g.create_time, g.modify_time
+        SELECT g.id, g.name, e.name as entity_name, g.category, g.entity_id, g.snapshot_interval, g.description, g.online_revision_id, g.create_time, g.modify_time
         FROM feature_group as g LEFT JOIN entity as e on g.entity_id = e.id
     "#.to_string();
 
@@ -442,7 +515,7 @@ where
     let mut conn = conn.acquire().await?;
 
     let mut query_str = r#"
-        SELECT g.id, g.name, e.name as entity_name, g.category, g.entity_id, g.snapshot_interval, g.description, g.create_time, g.modify_time
+        SELECT g.id, g.name, e.name as entity_name, g.category, g.entity_id, g.snapshot_interval, g.description, g.online_revision_id, g.create_time, g.modify_time
         FROM feature_group as g LEFT JOIN entity as e on g.entity_id = e.id"#.to_string();
 
     let query = match opt {
@@ -494,7 +567,7 @@ where
     A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
 {
     let mut conn = conn.acquire().await?;
-    let query = "SELECT id, name, category, entity_id, snapshot_interval, description, create_time, modify_time FROM feature_group".to_string();
+    let query = "SELECT id, name, category, entity_id, snapshot_interval, description, online_revision_id, create_time, modify_time FROM feature_group".to_string();
 
     let (cond, ids) = build_list_group_cond(&opt);
     if ids.is_empty() {
@@ -606,11 +679,8 @@ where
             return Ok(Vec::new());
         }
 
-        query = format!(
-            "{query} WHERE {} in (?{})",
-            cond,
-            ", ?".repeat(ids.len() - 1)
-        );
+        let placeholders = format!("?{}", ", ?".repeat(ids.len() - 1));
+        query = format!("{query} WHERE {}", cond.replace('?', &placeholders));
         let mut query = sqlx::query(&query);
         for id in ids {
             query = query.bind(id);
@@ -629,11 +699,235 @@ where
         .map_err(|e| e.into())
 }
 
+/// The condition on features and the ids it binds, its `?` stands for the list of ids.
 fn build_list_feature_cond(opt: &ListFeatureOpt) -> Option<(&'static str, &Vec<i64>)> {
     match opt {
-        ListFeatureOpt::GroupIDs(ids) => Some(("group_id", ids)),
-        ListFeatureOpt::FeatureIDs(ids) => Some(("id", ids)),
-        ListFeatureOpt::EntityIDs(_) => None,
+        ListFeatureOpt::All => None,
+        ListFeatureOpt::GroupIDs(ids) => Some(("group_id in (?)", ids)),
+        ListFeatureOpt::FeatureIDs(ids) => Some(("id in (?)", ids)),
+        ListFeatureOpt::EntityIDs(ids) => Some((
+            "group_id in (SELECT id FROM feature_group WHERE entity_id in (?))",
+            ids,
+        )),
+    }
+}
+
+/// Deletes the pruned features, then the pruned groups and entities along with what they have.
+async fn prune_internal(conn: &mut SqliteConnection, prune: &Prune) -> Result<()> {
+    if prune.is_empty() {
+        return Ok(());
+    }
+
+    let groups = list_group(&mut *conn, ListOpt::All).await?;
+    for feature in list_feature2(&mut *conn, ListFeatureOpt::All).await? {
+        let Some(group) = groups.iter().find(|g| g.id == feature.group_id) else {
+            continue;
+        };
+        let full_name = format!("{}.{}", group.name, feature.name);
+        if prune.features.contains(&full_name) {
+            delete_feature(&mut *conn, feature.id)
+                .await
+                .map_err(applying("feature", &full_name))?;
+        }
+    }
+
+    for group in groups.iter().filter(|g| prune.groups.contains(&g.name)) {
+        delete_group(&mut *conn, group.id, true)
+            .await
+            .map_err(applying("group", &group.name))?;
+    }
+
+    for entity in list_entity(&mut *conn, ListOpt::All).await? {
+        if prune.entities.contains(&entity.name) {
+            delete_entity(&mut *conn, entity.id, true)
+                .await
+                .map_err(applying("entity", &entity.name))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Tells which object of a plan failed to apply.
+fn applying(kind: &'static str, name: &str) -> impl FnOnce(Error) -> Error {
+    let object = plan::object(kind, name);
+    move |source| Error::Apply {
+        object,
+        source: Box::new(source),
+    }
+}
+
+/// Deletes an entity, and if `cascade`, its groups along with their features and
+/// revisions. An entity with groups isn't deleted otherwise.
+async fn delete_entity<'a, A>(conn: A, id: i64, cascade: bool) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let name: String = sqlx::query_scalar("SELECT name FROM entity WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| Error::ColumnNotFound("entity".to_owned(), id.to_string()))?;
+
+    let groups: Vec<(i64, String)> =
+        sqlx::query_as("SELECT id, name FROM feature_group WHERE entity_id = ? ORDER BY id")
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+    if !groups.is_empty() && !cascade {
+        return Err(Error::Other(format!(
+            "entity '{name}' still has groups: {}",
+            groups.iter().map(|(_, name)| name.as_str()).join(", ")
+        )));
+    }
+    for (group_id, _) in groups {
+        delete_group(&mut *conn, group_id, true).await?;
+    }
+
+    sqlx::query("DELETE FROM entity WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Deletes a group and its revisions, and if `cascade`, its features. A group with
+/// features isn't deleted otherwise.
+async fn delete_group<'a, A>(conn: A, id: i64, cascade: bool) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let name: String = sqlx::query_scalar("SELECT name FROM feature_group WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| Error::ColumnNotFound("feature_group".to_owned(), id.to_string()))?;
+
+    let features: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM feature WHERE group_id = ? ORDER BY id")
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+    if !features.is_empty() && !cascade {
+        return Err(Error::Other(format!(
+            "group '{name}' still has features: {}",
+            features.join(", ")
+        )));
+    }
+
+    for query in [
+        "DELETE FROM feature WHERE group_id = ?",
+        "DELETE FROM revision WHERE group_id = ?",
+        "DELETE FROM feature_group WHERE id = ?",
+    ] {
+        sqlx::query(query).bind(id).execute(&mut *conn).await?;
+    }
+    Ok(())
+}
+
+async fn delete_feature<'a, A>(conn: A, id: i64) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+    let rows_affected = sqlx::query("DELETE FROM feature WHERE id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+    if rows_affected != 1 {
+        Err(Error::ColumnNotFound("feature".to_owned(), id.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+async fn create_revision<'a, A>(conn: A, opt: CreateRevisionOpt) -> Result<i64>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+    let res = sqlx::query(
+        "INSERT INTO revision (group_id, revision, data_table, anchored, description, watermark) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(opt.group_id)
+    .bind(opt.revision)
+    .bind(&opt.data_table)
+    .bind(opt.anchored)
+    .bind(opt.description)
+    .bind(opt.watermark)
+    .execute(&mut *conn)
+    .await;
+
+    match res {
+        Err(sqlx::Error::Database(e)) => {
+            if e.message() == "UNIQUE constraint failed: revision.group_id, revision.revision" {
+                Err(Error::ColumnAlreadyExist(format!(
+                    "revision {}",
+                    opt.revision
+                )))
+            } else {
+                Err(e.to_string().into())
+            }
+        }
+        _ => Ok(res?.last_insert_rowid()),
+    }
+}
+
+async fn get_revision<'a, A>(conn: A, opt: GetRevisionOpt) -> Result<Option<Revision>>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let query = match opt {
+        GetRevisionOpt::ID(id) => sqlx::query_as("SELECT * FROM revision WHERE id = ?").bind(id),
+        GetRevisionOpt::Revision { group_id, revision } => {
+            sqlx::query_as("SELECT * FROM revision WHERE group_id = ? AND revision = ?")
+                .bind(group_id)
+                .bind(revision)
+        }
+    };
+
+    Ok(query.fetch_optional(&mut *conn).await?)
+}
+
+async fn list_revision<'a, A>(conn: A, opt: ListRevisionOpt) -> Result<Vec<Revision>>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let (cond, ids) = build_list_revision_cond(&opt);
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let query = format!(
+        "SELECT * FROM revision WHERE {cond} in (?{}) ORDER BY group_id, revision",
+        ", ?".repeat(ids.len() - 1)
+    );
+    let mut query = sqlx::query(&query);
+    for id in ids {
+        query = query.bind(id);
+    }
+    query
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .map(Revision::from_row)
+        .collect::<std::result::Result<Vec<Revision>, sqlx::Error>>()
+        .map_err(|e| e.into())
+}
+
+fn build_list_revision_cond(opt: &ListRevisionOpt) -> (&'static str, &Vec<i64>) {
+    match opt {
+        ListRevisionOpt::GroupIDs(ids) => ("group_id", ids),
+        ListRevisionOpt::RevisionIDs(ids) => ("id", ids),
     }
 }
 
@@ -643,6 +937,7 @@ mod tests {
 
     use crate::database::error::Error;
     use crate::database::metadata::types::{Category, ValueType};
+    use crate::feastore::Update;
     use sqlx::SqlitePool;
 
     async fn prepare_db(pool: SqlitePool) -> DB {
@@ -681,7 +976,7 @@ mod tests {
         assert_eq!(entity.name, "name");
         assert_eq!(entity.description, "description");
 
-        let entity = super::get_entity(&db.pool, GetOpt::Name(&"name"))
+        let entity = super::get_entity(&db.pool, GetOpt::Name("name"))
             .await
             .unwrap()
             .unwrap();
@@ -689,7 +984,7 @@ mod tests {
         assert_eq!(entity.name, "name");
         assert_eq!(entity.description, "description");
 
-        let res = super::get_entity(&db.pool, GetOpt::Name(&"not_exist"))
+        let res = super::get_entity(&db.pool, GetOpt::Name("not_exist"))
             .await
             .unwrap();
         assert!(res.is_none());
@@ -871,7 +1166,7 @@ mod tests {
         let res = super::get_group(&db.pool, GetOpt::ID(id + 1)).await;
         assert!(res.is_ok_and(|res| res.is_none()));
 
-        let res = super::get_group(&db.pool, GetOpt::Name(&"not_exist")).await;
+        let res = super::get_group(&db.pool, GetOpt::Name("not_exist")).await;
         assert!(res.is_ok_and(|res| res.is_none()));
     }
 
@@ -931,6 +1226,98 @@ mod tests {
         }));
     }
 
+    #[sqlx::test]
+    async fn update_online_revision(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+
+        assert!(
+            super::update_online_revision(&db.pool, 1, 1)
+                .await
+                .is_err_and(
+                    |e| e == Error::ColumnNotFound("feature_group".to_owned(), "1".to_owned())
+                )
+        );
+
+        let group_id = prepare_group(&db, "phone").await;
+        let group = super::get_group(&db.pool, GetOpt::ID(group_id))
+            .await
+            .unwrap();
+        assert!(group.is_some_and(|g| g.online_revision_id.is_none()));
+
+        let revision_id = super::create_revision(&db.pool, revision_opt(group_id, 1000))
+            .await
+            .unwrap();
+        super::update_online_revision(&db.pool, group_id, revision_id)
+            .await
+            .unwrap();
+        let group = super::get_group(&db.pool, GetOpt::ID(group_id))
+            .await
+            .unwrap();
+        assert!(group.is_some_and(|g| g.online_revision_id == Some(revision_id)));
+    }
+
+    #[sqlx::test]
+    async fn open_old_schema(pool: SqlitePool) {
+        // feature_group as created before online_revision_id was added
+        sqlx::query(
+            r#"
+            CREATE TABLE feature_group (
+                id                INTEGER         NOT NULL PRIMARY KEY AUTOINCREMENT,
+                name              VARCHAR(32)     NOT NULL,
+                category          VARCHAR(16)     NOT NULL,
+                entity_id         INT             NOT NULL,
+                snapshot_interval INT,
+                description       VARCHAR(64)     DEFAULT '',
+                create_time       TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                modify_time       TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(name),
+                FOREIGN KEY (entity_id) REFERENCES entity(id)
+            )"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // revision as created before watermark was added
+        sqlx::query(
+            r#"
+            CREATE TABLE revision (
+                id              INTEGER         NOT NULL PRIMARY KEY AUTOINCREMENT,
+                group_id        INT             NOT NULL,
+                revision        BIGINT          NOT NULL,
+                data_table      VARCHAR(64)     NOT NULL,
+                anchored        BOOLEAN         NOT NULL,
+                description     VARCHAR(64)     DEFAULT '',
+                create_time     TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                modify_time     TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (group_id, revision),
+                FOREIGN KEY (group_id) REFERENCES feature_group(id)
+            )"#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let db = prepare_db(pool).await;
+        // opening it again is a no-op
+        db.create_schemas().await;
+
+        let group_id = prepare_group(&db, "phone").await;
+        let mut opt = revision_opt(group_id, 1000);
+        opt.watermark = Some(7);
+        let revision_id = super::create_revision(&db.pool, opt).await.unwrap();
+        super::update_online_revision(&db.pool, group_id, revision_id)
+            .await
+            .unwrap();
+        let group = super::get_group(&db.pool, GetOpt::ID(group_id))
+            .await
+            .unwrap();
+        assert!(group.is_some_and(|g| g.online_revision_id == Some(revision_id)));
+        let revision = super::get_revision(&db.pool, GetRevisionOpt::ID(revision_id))
+            .await
+            .unwrap();
+        assert!(revision.is_some_and(|r| r.watermark == Some(7)));
+    }
+
     #[sqlx::test]
     async fn list_group(pool: SqlitePool) {
         let db = prepare_db(pool).await;
@@ -1109,7 +1496,7 @@ mod tests {
         assert_eq!(feature.group_id, group_id);
         assert_eq!(feature.description, "description".to_owned());
 
-        let feature = super::get_feature(&db.pool, GetOpt::Name(&"feature"))
+        let feature = super::get_feature(&db.pool, GetOpt::Name("feature"))
             .await
             .unwrap()
             .unwrap();
@@ -1178,6 +1565,235 @@ mod tests {
         }));
     }
 
+    /// Creates entity `user` with groups `account` and `click`, which has feature `clicks`
+    /// and a revision. Returns the ids of the entity, the groups and the feature.
+    async fn prepare_user(db: &DB) -> (i64, i64, i64, i64) {
+        let entity_id = super::create_entity(&db.pool, "user", "description")
+            .await
+            .unwrap();
+        let group = |name: &str| CreateGroupOpt {
+            entity_id,
+            category: Category::Batch,
+            snapshot_interval: None,
+            name: name.to_owned(),
+            description: "description".to_owned(),
+        };
+        let account_id = super::create_group(&db.pool, group("account"))
+            .await
+            .unwrap();
+        let click_id = super::create_group(&db.pool, group("click")).await.unwrap();
+        let feature_id = super::create_feature(
+            &db.pool,
+            CreateFeatureOpt {
+                group_id: click_id,
+                feature_name: "clicks".to_owned(),
+                description: "description".to_owned(),
+                value_type: ValueType::Int64,
+            },
+        )
+        .await
+        .unwrap();
+        super::create_revision(&db.pool, revision_opt(click_id, 1000))
+            .await
+            .unwrap();
+
+        (entity_id, account_id, click_id, feature_id)
+    }
+
+    #[sqlx::test]
+    async fn delete_entity(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+
+        assert_eq!(
+            db.delete_entity(1, false).await,
+            Err(Error::ColumnNotFound("entity".to_owned(), "1".to_owned()))
+        );
+
+        let (entity_id, _, click_id, _) = prepare_user(&db).await;
+        assert_eq!(
+            db.delete_entity(entity_id, false).await,
+            Err(Error::Other(
+                "entity 'user' still has groups: account, click".to_owned()
+            ))
+        );
+        assert!(super::get_entity(&db.pool, GetOpt::ID(entity_id))
+            .await
+            .unwrap()
+            .is_some());
+
+        db.delete_entity(entity_id, true).await.unwrap();
+        assert!(super::get_entity(&db.pool, GetOpt::ID(entity_id))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db.list_group(ListOpt::All).await.unwrap().is_empty());
+        assert!(db
+            .list_feature(ListFeatureOpt::GroupIDs(vec![click_id]))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(db
+            .list_revision(ListRevisionOpt::GroupIDs(vec![click_id]))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[sqlx::test]
+    async fn delete_group(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+
+        assert_eq!(
+            db.delete_group(1, false).await,
+            Err(Error::ColumnNotFound(
+                "feature_group".to_owned(),
+                "1".to_owned()
+            ))
+        );
+
+        let (_, account_id, click_id, _) = prepare_user(&db).await;
+        db.delete_group(account_id, false).await.unwrap();
+        assert_eq!(
+            db.delete_group(click_id, false).await,
+            Err(Error::Other(
+                "group 'click' still has features: clicks".to_owned()
+            ))
+        );
+
+        db.delete_group(click_id, true).await.unwrap();
+        assert!(db.list_group(ListOpt::All).await.unwrap().is_empty());
+        assert!(db
+            .list_revision(ListRevisionOpt::GroupIDs(vec![click_id]))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[sqlx::test]
+    async fn delete_feature(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+
+        let (_, _, click_id, feature_id) = prepare_user(&db).await;
+        db.delete_feature(feature_id).await.unwrap();
+        assert!(db
+            .list_feature(ListFeatureOpt::GroupIDs(vec![click_id]))
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            db.delete_feature(feature_id).await,
+            Err(Error::ColumnNotFound(
+                "feature".to_owned(),
+                feature_id.to_string()
+            ))
+        );
+    }
+
+    fn rich_group(name: &str, entity_name: &str) -> RichGroup {
+        RichGroup {
+            kind: None,
+            name: name.to_owned(),
+            entity_name: Some(entity_name.to_owned()),
+            category: Category::Stream,
+            snapshot_interval: Some(60),
+            description: "description".to_owned(),
+            features: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn apply(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+        let (entity_id, account_id, click_id, _) = prepare_user(&db).await;
+
+        let plan = ApplyPlan {
+            create_entities: vec![RichEntity {
+                name: "shop".to_owned(),
+                description: "description".to_owned(),
+                groups: None,
+            }],
+            create_groups: vec![rich_group("order", "shop")],
+            create_features: vec![RichFeature {
+                kind: None,
+                name: "amount".to_owned(),
+                group_name: Some("order".to_owned()),
+                value_type: ValueType::Float64,
+                description: "description".to_owned(),
+            }],
+            update_entities: vec![Update {
+                id: entity_id,
+                name: "user".to_owned(),
+                change: Change::Description {
+                    from: "description".to_owned(),
+                    to: "a user".to_owned(),
+                },
+            }],
+            update_groups: vec![Update {
+                id: click_id,
+                name: "click".to_owned(),
+                change: Change::SnapshotInterval {
+                    from: None,
+                    to: Some(3600),
+                },
+            }],
+            prune: Prune {
+                groups: vec!["account".to_owned()],
+                features: vec!["click.clicks".to_owned()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        db.apply(&plan).await.unwrap();
+
+        let order = super::get_group(&db.pool, GetOpt::Name("order"))
+            .await
+            .unwrap();
+        assert!(order.is_some_and(|g| g.entity_name == "shop" && g.snapshot_interval == Some(60)));
+        let features = db.list_feature(ListFeatureOpt::All).await.unwrap();
+        assert_eq!(
+            features.iter().map(|f| f.full_name()).collect::<Vec<_>>(),
+            vec!["order.amount"]
+        );
+        let user = super::get_entity(&db.pool, GetOpt::ID(entity_id))
+            .await
+            .unwrap();
+        assert!(user.is_some_and(|e| e.description == "a user"));
+        let click = super::get_group(&db.pool, GetOpt::ID(click_id))
+            .await
+            .unwrap();
+        assert!(click.is_some_and(|g| g.snapshot_interval == Some(3600)));
+        let account = super::get_group(&db.pool, GetOpt::ID(account_id))
+            .await
+            .unwrap();
+        assert!(account.is_none());
+    }
+
+    #[sqlx::test]
+    async fn apply_failure(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+
+        let plan = ApplyPlan {
+            create_entities: vec![RichEntity {
+                name: "shop".to_owned(),
+                description: "description".to_owned(),
+                groups: None,
+            }],
+            create_groups: vec![rich_group("order", "user")],
+            ..Default::default()
+        };
+        assert_eq!(
+            db.apply(&plan).await,
+            Err(Error::Apply {
+                object: "group 'order'".to_owned(),
+                source: Box::new(Error::Other("entity 'user' not found".to_owned())),
+            })
+        );
+
+        // the apply is rolled back as a whole
+        assert!(db.list_entity(ListOpt::All).await.unwrap().is_empty());
+    }
+
     #[sqlx::test]
     async fn list_feature(pool: SqlitePool) {
         let db = prepare_db(pool).await;
@@ -1249,6 +1865,16 @@ mod tests {
             .unwrap();
         assert_eq!(features.len(), 2);
 
+        let features = super::list_feature2(&db.pool, ListFeatureOpt::EntityIDs(vec![2]))
+            .await
+            .unwrap();
+        assert!(features.is_empty());
+
+        let features = super::list_feature2(&db.pool, ListFeatureOpt::All)
+            .await
+            .unwrap();
+        assert_eq!(features.len(), 2);
+
         // let features = super::list_feature2(
         //     &db.pool,
         //     ListOpt::Names(vec![&"feature_name", &"feature_name2", &"feature_name3"]),
@@ -1257,4 +1883,147 @@ mod tests {
         // .unwrap();
         // assert_eq!(features.len(), 2);
     }
+
+    #[sqlx::test]
+    async fn create_revision(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+        let group_id = prepare_group(&db, "group").await;
+
+        let res = super::create_revision(&db.pool, revision_opt(group_id, 1000)).await;
+        assert!(res.is_ok_and(|id| id == 1));
+
+        let res = super::create_revision(&db.pool, revision_opt(group_id, 2000)).await;
+        assert!(res.is_ok_and(|id| id == 2));
+
+        let res = super::create_revision(&db.pool, revision_opt(group_id, 1000)).await;
+        assert!(res.is_err_and(|e| match e {
+            Error::ColumnAlreadyExist(name) => name == "revision 1000",
+            _ => false,
+        }));
+    }
+
+    #[sqlx::test]
+    async fn get_revision(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+        let group_id = prepare_group(&db, "group").await;
+
+        let id = super::create_revision(&db.pool, revision_opt(group_id, 1000))
+            .await
+            .unwrap();
+
+        let revision = super::get_revision(&db.pool, GetRevisionOpt::ID(id))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(revision.id, id);
+        assert_eq!(revision.group_id, group_id);
+        assert_eq!(revision.revision, 1000);
+        assert_eq!(revision.data_table, "offline_batch_1_1000");
+        assert!(revision.anchored);
+        assert_eq!(revision.description, "description");
+
+        let revision = super::get_revision(
+            &db.pool,
+            GetRevisionOpt::Revision {
+                group_id,
+                revision: 1000,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(revision.is_some_and(|r| r.id == id));
+
+        let res = super::get_revision(&db.pool, GetRevisionOpt::ID(id + 1)).await;
+        assert!(res.is_ok_and(|res| res.is_none()));
+
+        let res = super::get_revision(
+            &db.pool,
+            GetRevisionOpt::Revision {
+                group_id,
+                revision: 2000,
+            },
+        )
+        .await;
+        assert!(res.is_ok_and(|res| res.is_none()));
+    }
+
+    #[sqlx::test]
+    async fn list_revision(pool: SqlitePool) {
+        let db = prepare_db(pool).await;
+        let group_id = prepare_group(&db, "group").await;
+        let other_group_id = prepare_group(&db, "other_group").await;
+
+        let revisions = super::list_revision(&db.pool, ListRevisionOpt::GroupIDs(vec![group_id]))
+            .await
+            .unwrap();
+        assert_eq!(revisions.len(), 0);
+
+        for (group_id, revision) in [(group_id, 3000), (group_id, 1000), (other_group_id, 2000)] {
+            super::create_revision(&db.pool, revision_opt(group_id, revision))
+                .await
+                .unwrap();
+        }
+
+        let revisions = super::list_revision(&db.pool, ListRevisionOpt::GroupIDs(vec![group_id]))
+            .await
+            .unwrap();
+        assert_eq!(
+            revisions.iter().map(|r| r.revision).collect::<Vec<_>>(),
+            vec![1000, 3000]
+        );
+
+        let revisions = super::list_revision(
+            &db.pool,
+            ListRevisionOpt::GroupIDs(vec![group_id, other_group_id]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(revisions.len(), 3);
+
+        let revisions = super::list_revision(&db.pool, ListRevisionOpt::RevisionIDs(vec![1, 3, 4]))
+            .await
+            .unwrap();
+        assert_eq!(revisions.len(), 2);
+
+        let revisions = super::list_revision(&db.pool, ListRevisionOpt::GroupIDs(Vec::new()))
+            .await
+            .unwrap();
+        assert_eq!(revisions.len(), 0);
+    }
+
+    async fn prepare_group(db: &DB, name: &str) -> i64 {
+        let entity_id = match super::get_entity(&db.pool, GetOpt::Name("entity"))
+            .await
+            .unwrap()
+        {
+            Some(entity) => entity.id,
+            None => super::create_entity(&db.pool, "entity", "description")
+                .await
+                .unwrap(),
+        };
+
+        super::create_group(
+            &db.pool,
+            CreateGroupOpt {
+                entity_id,
+                category: Category::Batch,
+                snapshot_interval: None,
+                name: name.to_owned(),
+                description: "description".to_owned(),
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    fn revision_opt(group_id: i64, revision: i64) -> CreateRevisionOpt {
+        CreateRevisionOpt {
+            group_id,
+            revision,
+            data_table: format!("offline_batch_{group_id}_{revision}"),
+            anchored: true,
+            description: "description".to_owned(),
+            watermark: None,
+        }
+    }
 }
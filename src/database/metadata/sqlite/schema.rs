@@ -1,5 +1,5 @@
 use phf::phf_map;
-use sqlx::SqlitePool;
+use sqlx::{Row, SqlitePool};
 
 pub static META_TABLE_SCHEMAS: phf::Map<&'static str, &'static str> = phf_map! {
     "entity" => r#"
@@ -20,6 +20,7 @@ pub static META_TABLE_SCHEMAS: phf::Map<&'static str, &'static str> = phf_map! {
             entity_id         INT             NOT NULL,
             snapshot_interval INT,
             description       VARCHAR(64)     DEFAULT '',
+            online_revision_id INT,
             create_time       TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
             modify_time       TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
             UNIQUE(name),
@@ -39,8 +40,30 @@ pub static META_TABLE_SCHEMAS: phf::Map<&'static str, &'static str> = phf_map! {
             FOREIGN KEY (group_id) REFERENCES feature_group(id)
         )
     "#,
+    "revision" => r#"
+        CREATE TABLE IF NOT EXISTS revision (
+            id              INTEGER         NOT NULL PRIMARY KEY AUTOINCREMENT,
+            group_id        INT             NOT NULL,
+            revision        BIGINT          NOT NULL,
+            data_table      VARCHAR(64)     NOT NULL,
+            anchored        BOOLEAN         NOT NULL,
+            description     VARCHAR(64)     DEFAULT '',
+            watermark       BIGINT,
+            create_time     TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            modify_time     TIMESTAMP       NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (group_id, revision),
+            FOREIGN KEY (group_id) REFERENCES feature_group(id)
+        )
+    "#,
 };
 
+/// Columns added to tables after they were first created, as (table, column, definition).
+/// `CREATE TABLE IF NOT EXISTS` leaves an existing table as it is, so they are added on open.
+pub static META_TABLE_COLUMNS: &[(&str, &str, &str)] = &[
+    ("feature_group", "online_revision_id", "INT"),
+    ("revision", "watermark", "BIGINT"),
+];
+
 pub static META_VIEW_SCHEMAS: phf::Map<&'static str, &'static str> = phf_map! {};
 
 pub async fn create_tables(pool: &SqlitePool) {
@@ -52,6 +75,25 @@ pub async fn create_tables(pool: &SqlitePool) {
     }
 }
 
+pub async fn add_columns(pool: &SqlitePool) {
+    for (table, column, definition) in META_TABLE_COLUMNS {
+        let exists = sqlx::query(&format!("PRAGMA table_info({table})"))
+            .fetch_all(pool)
+            .await
+            .unwrap_or_else(|_| panic!("get columns of {} failed!", table))
+            .iter()
+            .any(|row| row.get::<String, _>("name") == *column);
+        if !exists {
+            sqlx::query(&format!(
+                "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+            ))
+            .execute(pool)
+            .await
+            .unwrap_or_else(|_| panic!("add column {}.{} failed!", table, column));
+        }
+    }
+}
+
 pub async fn create_views(pool: &SqlitePool) {
     for view_schema in META_VIEW_SCHEMAS.values() {
         sqlx::query(view_schema).execute(pool).await.unwrap();
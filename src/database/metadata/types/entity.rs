@@ -14,7 +14,7 @@ pub struct Entity {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-#[serde(tag = "kind", rename = "Entity")]
+#[serde(tag = "kind", rename = "Entity", deny_unknown_fields)]
 pub struct RichEntity {
     pub name: String,
     pub description: String,
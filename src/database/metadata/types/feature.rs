@@ -1,9 +1,12 @@
+use std::fmt;
+
 use chrono::{DateTime, Utc};
 use clap::builder::PossibleValue;
 use clap::ValueEnum;
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 
-use super::Group2;
+use super::{FeatureValue, Group2};
+use crate::database::{Error, Result as DatabaseResult};
 
 #[derive(sqlx::FromRow, Clone)]
 pub struct Feature {
@@ -57,14 +60,28 @@ impl Feature {
     }
 
     pub fn group_name(&self) -> Option<String> {
-        match self.group {
-            Some(ref group) => Some(group.name.to_string()),
-            None => None,
+        self.group.as_ref().map(|group| group.name.to_string())
+    }
+
+    /// Parses a raw string into a value of this feature. An empty string is null,
+    /// except for a string feature, where it's the empty string.
+    pub fn parse_value(&self, raw: &str) -> DatabaseResult<Option<FeatureValue>> {
+        if raw.is_empty() && self.value_type != ValueType::StringType {
+            return Ok(None);
         }
+
+        FeatureValue::parse(&self.value_type, raw)
+            .map(Some)
+            .map_err(|reason| Error::InvalidValue {
+                feature: self.full_name(),
+                value: raw.to_owned(),
+                reason,
+            })
     }
 }
 
 #[derive(sqlx::FromRow, Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct RichFeature {
     // just for printing, the value is always None or Some("Group")
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -128,6 +145,7 @@ pub enum ValueType {
     Bool,
     Time,
     Bytes,
+    #[serde(skip_deserializing)]
     Invalid,
 }
 
@@ -155,3 +173,26 @@ impl ValueEnum for ValueType {
         }
     }
 }
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => write!(f, "invalid"),
+        }
+    }
+}
+
+impl ValueType {
+    /// The column type used to store values of this type in SQLite.
+    pub(crate) fn sqlite_type(&self) -> &'static str {
+        match self {
+            Self::StringType => "TEXT",
+            Self::Int64 => "INTEGER",
+            Self::Float64 => "REAL",
+            Self::Bool => "BOOLEAN",
+            Self::Time => "DATETIME",
+            Self::Bytes | Self::Invalid => "BLOB",
+        }
+    }
+}
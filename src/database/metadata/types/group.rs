@@ -1,3 +1,5 @@
+use std::fmt;
+
 use chrono::{DateTime, Utc};
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
@@ -16,6 +18,10 @@ pub struct Group {
     pub snapshot_interval: Option<i32>, // FIXME: use chrono::Duration repleace i32
 
     pub description: String,
+
+    #[serde(rename(serialize = "online-revision-id", deserialize = "online-revision-id"))]
+    pub online_revision_id: Option<i64>,
+
     pub create_time: DateTime<Utc>,
     pub modify_time: DateTime<Utc>,
 
@@ -30,6 +36,7 @@ pub struct Group2 {
     pub category: Category,
     pub snapshot_interval: Option<i32>, // FIXME: use chrono::Duration repleace i32
     pub description: String,
+    pub online_revision_id: Option<i64>,
     pub create_time: DateTime<Utc>,
     pub modify_time: DateTime<Utc>,
 
@@ -38,6 +45,7 @@ pub struct Group2 {
 }
 
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct RichGroup {
     // just for printing, the value is always None or Some("Group")
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -131,3 +139,12 @@ pub enum Category {
     Batch,
     Stream,
 }
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => write!(f, "invalid"),
+        }
+    }
+}
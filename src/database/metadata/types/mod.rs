@@ -2,6 +2,8 @@ mod entity;
 mod feature;
 mod group;
 mod opt;
+mod revision;
+mod value;
 
 pub use entity::Entity;
 pub use entity::RichEntity;
@@ -17,7 +19,15 @@ pub use feature::Feature;
 pub use feature::RichFeature;
 pub use feature::ValueType;
 
+pub use value::FeatureValue;
+pub use value::FeatureValueSeed;
+
+pub use revision::CreateRevisionOpt;
+pub use revision::Revision;
+
 pub use opt::GetOpt;
+pub use opt::GetRevisionOpt;
 pub use opt::ListFeatureOpt;
 pub use opt::ListGroupOpt;
 pub use opt::ListOpt;
+pub use opt::ListRevisionOpt;
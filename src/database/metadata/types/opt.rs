@@ -31,7 +31,20 @@ pub enum ListGroupOpt {
 
 #[derive(Debug)]
 pub enum ListFeatureOpt {
+    All,
     EntityIDs(Vec<i64>),
     GroupIDs(Vec<i64>),
     FeatureIDs(Vec<i64>),
 }
+
+#[derive(Debug)]
+pub enum GetRevisionOpt {
+    ID(i64),
+    Revision { group_id: i64, revision: i64 },
+}
+
+#[derive(Debug)]
+pub enum ListRevisionOpt {
+    GroupIDs(Vec<i64>),
+    RevisionIDs(Vec<i64>),
+}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(sqlx::FromRow, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    pub id: i64,
+    pub group_id: i64,
+    /// unix milliseconds since when the data of the revision takes effect
+    pub revision: i64,
+    pub data_table: String,
+    /// whether the revision was given explicitly rather than taken from the import time
+    pub anchored: bool,
+    pub description: String,
+    /// for a snapshot of a stream group, the sequence number of the last log row merged
+    /// into it, in the order rows are pushed. Rows after it are merged on top of it, even
+    /// if their event time is earlier, e.g. late events
+    pub watermark: Option<i64>,
+    pub create_time: DateTime<Utc>,
+    pub modify_time: DateTime<Utc>,
+}
+
+pub struct CreateRevisionOpt {
+    pub group_id: i64,
+    pub revision: i64,
+    pub data_table: String,
+    pub anchored: bool,
+    pub description: String,
+    pub watermark: Option<i64>,
+}
@@ -0,0 +1,549 @@
+use std::fmt;
+
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::{self, DeserializeSeed, Visitor};
+use serde::{Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::sqlite::{SqliteArgumentValue, SqliteRow, SqliteTypeInfo};
+use sqlx::{Row, Sqlite};
+
+use super::{Feature, ValueType};
+
+/// A feature value typed by the `ValueType` of its feature.
+///
+/// The string form (`Display` and `parse`) and the serde form are lossless:
+/// times are RFC3339 in UTC, bytes are `0x` prefixed hex, and infinite floats
+/// are serialized in the string form, as JSON has no number for them. NaN is
+/// rejected, as SQLite stores it as NULL.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureValue {
+    String(String),
+    Int64(i64),
+    Float64(f64),
+    Bool(bool),
+    Time(DateTime<Utc>),
+    Bytes(Vec<u8>),
+}
+
+impl FeatureValue {
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::String(_) => ValueType::StringType,
+            Self::Int64(_) => ValueType::Int64,
+            Self::Float64(_) => ValueType::Float64,
+            Self::Bool(_) => ValueType::Bool,
+            Self::Time(_) => ValueType::Time,
+            Self::Bytes(_) => ValueType::Bytes,
+        }
+    }
+
+    /// Parses a raw string, e.g. a CSV cell, into a value of `value_type`.
+    ///
+    /// Times are either RFC3339 or unix milliseconds, bytes are either `0x`
+    /// prefixed hex or standard base64. The error is the reason only, see
+    /// `Feature::parse_value` for an error naming the feature.
+    pub fn parse(value_type: &ValueType, raw: &str) -> std::result::Result<Self, String> {
+        match value_type {
+            ValueType::StringType => Ok(Self::String(raw.to_owned())),
+            ValueType::Int64 => raw.parse().map(Self::Int64).map_err(|e| e.to_string()),
+            ValueType::Float64 => {
+                let value = raw.parse().map(Self::Float64).map_err(|e| e.to_string())?;
+                value.check().map(|()| value)
+            }
+            ValueType::Bool => match raw {
+                "true" => Ok(Self::Bool(true)),
+                "false" => Ok(Self::Bool(false)),
+                _ => Err("expected 'true' or 'false'".to_owned()),
+            },
+            ValueType::Time => match raw.parse::<i64>() {
+                Ok(unix_milli) => time_from_unix_milli(unix_milli).map(Self::Time),
+                Err(_) => DateTime::parse_from_rfc3339(raw)
+                    .map(|t| Self::Time(t.with_timezone(&Utc)))
+                    .map_err(|e| format!("expected an RFC3339 time or unix milliseconds, {e}")),
+            },
+            ValueType::Bytes => match raw.strip_prefix("0x") {
+                Some(digits) => hex::decode(digits)
+                    .map(Self::Bytes)
+                    .map_err(|e| format!("invalid hex, {e}")),
+                None => base64::engine::general_purpose::STANDARD
+                    .decode(raw)
+                    .map(Self::Bytes)
+                    .map_err(|e| format!("invalid base64, {e}")),
+            },
+            ValueType::Invalid => Err("invalid value type".to_owned()),
+        }
+    }
+
+    /// Checks that SQLite stores the value as is, i.e. it's not a NaN.
+    pub fn check(&self) -> std::result::Result<(), String> {
+        match self {
+            Self::Float64(v) if v.is_nan() => Err("NaN is not supported".to_owned()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Decodes the column at `index` of a SQLite row, NULL is decoded as `None`.
+    pub(crate) fn from_sqlite_row(
+        row: &SqliteRow,
+        index: usize,
+        value_type: &ValueType,
+    ) -> sqlx::Result<Option<Self>> {
+        let value = match value_type {
+            ValueType::StringType => row.try_get::<Option<String>, _>(index)?.map(Self::String),
+            ValueType::Int64 => row.try_get::<Option<i64>, _>(index)?.map(Self::Int64),
+            ValueType::Float64 => row.try_get::<Option<f64>, _>(index)?.map(Self::Float64),
+            ValueType::Bool => row.try_get::<Option<bool>, _>(index)?.map(Self::Bool),
+            ValueType::Time => row
+                .try_get::<Option<DateTime<Utc>>, _>(index)?
+                .map(Self::Time),
+            ValueType::Bytes => row.try_get::<Option<Vec<u8>>, _>(index)?.map(Self::Bytes),
+            ValueType::Invalid => return Err(sqlx::Error::Decode("invalid value type".into())),
+        };
+
+        Ok(value)
+    }
+}
+
+fn time_from_unix_milli(unix_milli: i64) -> std::result::Result<DateTime<Utc>, String> {
+    DateTime::from_timestamp_millis(unix_milli)
+        .ok_or_else(|| format!("unix milliseconds {unix_milli} out of range"))
+}
+
+impl fmt::Display for FeatureValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(v) => write!(f, "{v}"),
+            Self::Int64(v) => write!(f, "{v}"),
+            Self::Float64(v) => write!(f, "{v}"),
+            Self::Bool(v) => write!(f, "{v}"),
+            Self::Time(v) => write!(f, "{}", v.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            Self::Bytes(v) => write!(f, "0x{}", hex::encode(v)),
+        }
+    }
+}
+
+impl Serialize for FeatureValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::String(v) => serializer.serialize_str(v),
+            Self::Int64(v) => serializer.serialize_i64(*v),
+            Self::Float64(v) if v.is_finite() => serializer.serialize_f64(*v),
+            Self::Bool(v) => serializer.serialize_bool(*v),
+            Self::Float64(_) | Self::Time(_) | Self::Bytes(_) => serializer.collect_str(self),
+        }
+    }
+}
+
+/// Deserializes a nullable value of `feature`, e.g. from JSON or YAML.
+///
+/// Besides native numbers and booleans, every type accepts the string form
+/// of `FeatureValue::parse`, and times accept unix milliseconds as a number.
+pub struct FeatureValueSeed<'a>(pub &'a Feature);
+
+impl<'de> DeserializeSeed<'de> for FeatureValueSeed<'_> {
+    type Value = Option<FeatureValue>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl FeatureValueSeed<'_> {
+    fn invalid<E: de::Error>(&self, value: impl fmt::Display, reason: impl fmt::Display) -> E {
+        E::custom(format!(
+            "invalid value '{value}' of feature '{}': {reason}",
+            self.0.full_name()
+        ))
+    }
+
+    fn unexpected<E: de::Error>(&self, value: impl fmt::Display) -> E {
+        self.invalid(value, format!("expected {}", self.0.value_type))
+    }
+}
+
+impl<'de> Visitor<'de> for FeatureValueSeed<'_> {
+    type Value = Option<FeatureValue>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a {} value or null", self.0.value_type)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        match self.0.value_type {
+            ValueType::Bool => Ok(Some(FeatureValue::Bool(v))),
+            _ => Err(self.unexpected(v)),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        match self.0.value_type {
+            ValueType::Int64 => Ok(Some(FeatureValue::Int64(v))),
+            // only integers a float64 holds exactly
+            ValueType::Float64 if v as f64 as i128 == v as i128 => {
+                Ok(Some(FeatureValue::Float64(v as f64)))
+            }
+            ValueType::Float64 => Err(self.invalid(v, "not representable as float64")),
+            ValueType::Time => time_from_unix_milli(v)
+                .map(|t| Some(FeatureValue::Time(t)))
+                .map_err(|e| self.invalid(v, e)),
+            _ => Err(self.unexpected(v)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        match i64::try_from(v) {
+            Ok(v) => self.visit_i64(v),
+            Err(_) => Err(self.invalid(v, format!("out of range of {}", self.0.value_type))),
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        match self.0.value_type {
+            ValueType::Float64 => {
+                let value = FeatureValue::Float64(v);
+                value.check().map_err(|e| self.invalid(v, e))?;
+                Ok(Some(value))
+            }
+            _ => Err(self.unexpected(v)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        FeatureValue::parse(&self.0.value_type, v)
+            .map(Some)
+            .map_err(|e| self.invalid(v, e))
+    }
+}
+
+impl sqlx::Type<Sqlite> for FeatureValue {
+    fn type_info() -> SqliteTypeInfo {
+        <str as sqlx::Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> sqlx::Encode<'q, Sqlite> for FeatureValue {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        match self {
+            Self::String(v) => <String as sqlx::Encode<Sqlite>>::encode_by_ref(v, buf),
+            Self::Int64(v) => <i64 as sqlx::Encode<Sqlite>>::encode_by_ref(v, buf),
+            Self::Float64(v) => <f64 as sqlx::Encode<Sqlite>>::encode_by_ref(v, buf),
+            Self::Bool(v) => <bool as sqlx::Encode<Sqlite>>::encode_by_ref(v, buf),
+            Self::Time(v) => <DateTime<Utc> as sqlx::Encode<Sqlite>>::encode_by_ref(v, buf),
+            Self::Bytes(v) => <Vec<u8> as sqlx::Encode<Sqlite>>::encode_by_ref(v, buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+    use sqlx::SqlitePool;
+
+    use crate::database::metadata::types::{Category, Group2};
+
+    fn feature(value_type: ValueType) -> Feature {
+        Feature {
+            id: 1,
+            name: "f".to_owned(),
+            group_id: 1,
+            value_type,
+            description: String::new(),
+            create_time: Utc::now(),
+            modify_time: Utc::now(),
+            group: Some(Group2 {
+                id: 1,
+                name: "g".to_owned(),
+                category: Category::Batch,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn samples() -> Vec<FeatureValue> {
+        vec![
+            FeatureValue::String("".to_owned()),
+            FeatureValue::String("a,b \"c\"".to_owned()),
+            FeatureValue::Int64(i64::MIN),
+            FeatureValue::Int64(i64::MAX),
+            FeatureValue::Float64(0.1),
+            FeatureValue::Float64(-1.5e300),
+            FeatureValue::Float64(f64::INFINITY),
+            FeatureValue::Float64(f64::NEG_INFINITY),
+            FeatureValue::Bool(true),
+            FeatureValue::Bool(false),
+            FeatureValue::Time(Utc.timestamp_opt(1638259200, 0).unwrap()),
+            FeatureValue::Time(Utc.timestamp_opt(1638259200, 123456789).unwrap()),
+            FeatureValue::Bytes(vec![]),
+            FeatureValue::Bytes(vec![0, 1, 0xfe, 0xff]),
+        ]
+    }
+
+    #[test]
+    fn parse() {
+        let time = Utc.timestamp_millis_opt(1638259200123).unwrap();
+        let cases = vec![
+            (
+                ValueType::StringType,
+                " x ",
+                Ok(FeatureValue::String(" x ".to_owned())),
+            ),
+            (ValueType::Int64, "-42", Ok(FeatureValue::Int64(-42))),
+            (
+                ValueType::Int64,
+                "4.2",
+                Err("invalid digit found in string"),
+            ),
+            (
+                ValueType::Int64,
+                "",
+                Err("cannot parse integer from empty string"),
+            ),
+            (ValueType::Float64, "4.2", Ok(FeatureValue::Float64(4.2))),
+            (ValueType::Float64, "4,2", Err("invalid float literal")),
+            (ValueType::Float64, "NaN", Err("NaN is not supported")),
+            (ValueType::Bool, "true", Ok(FeatureValue::Bool(true))),
+            (ValueType::Bool, "1", Err("expected 'true' or 'false'")),
+            (
+                ValueType::Time,
+                "2021-11-30T16:00:00.123+08:00",
+                Ok(FeatureValue::Time(time)),
+            ),
+            (
+                ValueType::Time,
+                "1638259200123",
+                Ok(FeatureValue::Time(time)),
+            ),
+            (
+                ValueType::Time,
+                "2021-11-30",
+                Err("expected an RFC3339 time or unix milliseconds, premature end of input"),
+            ),
+            (
+                ValueType::Bytes,
+                "0x00ff",
+                Ok(FeatureValue::Bytes(vec![0, 0xff])),
+            ),
+            (
+                ValueType::Bytes,
+                "AP8=",
+                Ok(FeatureValue::Bytes(vec![0, 0xff])),
+            ),
+            (
+                ValueType::Bytes,
+                "0x0",
+                Err("invalid hex, Odd number of digits"),
+            ),
+            (
+                ValueType::Bytes,
+                "A",
+                Err("invalid base64, Invalid input length: 1"),
+            ),
+            (ValueType::Invalid, "x", Err("invalid value type")),
+        ];
+
+        for (value_type, raw, expected) in cases {
+            assert_eq!(
+                FeatureValue::parse(&value_type, raw),
+                expected.map_err(String::from),
+                "parse {raw} as {value_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_display_roundtrip() {
+        for value in samples() {
+            let parsed = FeatureValue::parse(&value.value_type(), &value.to_string());
+            assert_eq!(parsed, Ok(value));
+        }
+    }
+
+    #[test]
+    fn parse_value() {
+        let f = feature(ValueType::Int64);
+        assert_eq!(f.parse_value("7"), Ok(Some(FeatureValue::Int64(7))));
+        assert_eq!(f.parse_value(""), Ok(None));
+        assert_eq!(
+            f.parse_value("seven").unwrap_err().to_string(),
+            "invalid value 'seven' of feature 'g.f': invalid digit found in string"
+        );
+
+        // only a string feature has an empty value
+        let f = feature(ValueType::StringType);
+        assert_eq!(
+            f.parse_value(""),
+            Ok(Some(FeatureValue::String(String::new())))
+        );
+        assert_eq!(feature(ValueType::Bytes).parse_value(""), Ok(None));
+    }
+
+    #[test]
+    fn parse_value_roundtrip() {
+        for value in samples() {
+            let f = feature(value.value_type());
+            assert_eq!(f.parse_value(&value.to_string()), Ok(Some(value)));
+        }
+    }
+
+    #[test]
+    fn nan_rejected() {
+        let f = feature(ValueType::Float64);
+        assert_eq!(
+            f.parse_value("NaN").unwrap_err().to_string(),
+            "invalid value 'NaN' of feature 'g.f': NaN is not supported"
+        );
+
+        let de = serde_yaml::Deserializer::from_str(".nan");
+        let err = FeatureValueSeed(&f).deserialize(de).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid value 'NaN' of feature 'g.f': NaN is not supported"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn serde_json_roundtrip() {
+        for value in samples() {
+            let f = feature(value.value_type());
+            let json = serde_json::to_string(&value).unwrap();
+            let mut de = serde_json::Deserializer::from_str(&json);
+            assert_eq!(
+                FeatureValueSeed(&f).deserialize(&mut de).unwrap(),
+                Some(value)
+            );
+        }
+
+        let f = feature(ValueType::Time);
+        let value = FeatureValue::Time(Utc.timestamp_opt(1638259200, 0).unwrap());
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            "\"2021-11-30T08:00:00Z\""
+        );
+        let mut de = serde_json::Deserializer::from_str("1638259200000");
+        assert_eq!(
+            FeatureValueSeed(&f).deserialize(&mut de).unwrap(),
+            Some(value)
+        );
+        let mut de = serde_json::Deserializer::from_str("null");
+        assert_eq!(FeatureValueSeed(&f).deserialize(&mut de).unwrap(), None);
+    }
+
+    #[test]
+    fn serde_yaml_roundtrip() {
+        for value in samples() {
+            let f = feature(value.value_type());
+            let yaml = serde_yaml::to_string(&value).unwrap();
+            let de = serde_yaml::Deserializer::from_str(&yaml);
+            assert_eq!(FeatureValueSeed(&f).deserialize(de).unwrap(), Some(value));
+        }
+    }
+
+    #[test]
+    fn deserialize_invalid() {
+        let cases = vec![
+            (
+                ValueType::Int64,
+                "1.5",
+                "invalid value '1.5' of feature 'g.f': expected int64",
+            ),
+            (
+                ValueType::Int64,
+                "true",
+                "invalid value 'true' of feature 'g.f': expected int64",
+            ),
+            (
+                ValueType::Int64,
+                "\"x\"",
+                "invalid value 'x' of feature 'g.f': invalid digit found in string",
+            ),
+            (
+                ValueType::Int64,
+                "18446744073709551615",
+                "invalid value '18446744073709551615' of feature 'g.f': out of range of int64",
+            ),
+            (
+                ValueType::StringType,
+                "1",
+                "invalid value '1' of feature 'g.f': expected string",
+            ),
+            (
+                ValueType::Float64,
+                "9007199254740993",
+                "invalid value '9007199254740993' of feature 'g.f': not representable as float64",
+            ),
+            (
+                ValueType::Float64,
+                "9223372036854775807",
+                "invalid value '9223372036854775807' of feature 'g.f': not representable as float64",
+            ),
+            (
+                ValueType::Float64,
+                "\"NaN\"",
+                "invalid value 'NaN' of feature 'g.f': NaN is not supported",
+            ),
+            (
+                ValueType::Bool,
+                "\"yes\"",
+                "invalid value 'yes' of feature 'g.f': expected 'true' or 'false'",
+            ),
+        ];
+
+        for (value_type, json, expected) in cases {
+            let f = feature(value_type);
+            let mut de = serde_json::Deserializer::from_str(json);
+            let err = FeatureValueSeed(&f).deserialize(&mut de).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{err}");
+        }
+    }
+
+    #[sqlx::test]
+    async fn sqlite_roundtrip(pool: SqlitePool) {
+        for value in samples() {
+            let value_type = value.value_type();
+            let query = format!("CREATE TABLE t (v {})", value_type.sqlite_type());
+            sqlx::query(&query).execute(&pool).await.unwrap();
+
+            sqlx::query("INSERT INTO t VALUES (?), (NULL)")
+                .bind(value.clone())
+                .execute(&pool)
+                .await
+                .unwrap();
+            let rows = sqlx::query("SELECT v FROM t")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            let values = rows
+                .iter()
+                .map(|row| FeatureValue::from_sqlite_row(row, 0, &value_type).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(values, vec![Some(value), None]);
+
+            sqlx::query("DROP TABLE t").execute(&pool).await.unwrap();
+        }
+    }
+}
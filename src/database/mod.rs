@@ -1,9 +1,10 @@
 pub mod metadata;
-mod offline;
-mod online;
+pub(crate) mod offline;
+pub(crate) mod online;
 
 mod error;
 mod opt;
+mod util;
 
 pub use error::Error;
 pub use error::Result;
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use futures::stream::BoxStream;
+
+use crate::database::metadata::{Feature, FeatureValue};
+use crate::database::offline::sqlite;
+use crate::database::Result;
+use crate::feastore::{BackendOpt, EntityRow, Row};
+
+/// A data table whose rows are valid from `start` (inclusive) to `end` (exclusive),
+/// both in unix milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RevisionRange {
+    pub(crate) data_table: String,
+    pub(crate) start: i64,
+    pub(crate) end: i64,
+    /// the watermark of a snapshot, see [`SnapshotBase`]; unused for a batch group
+    pub(crate) watermark: i64,
+}
+
+/// A snapshot of a stream group to start from, which holds the latest row of each
+/// entity key as of `unix_milli` among the log rows up to `watermark`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SnapshotBase<'a> {
+    pub(crate) data_table: &'a str,
+    pub(crate) unix_milli: i64,
+    /// the sequence number of the last log row merged into the snapshot, in the order
+    /// rows are pushed. Later rows are replayed even if their event time isn't after
+    /// `unix_milli`, e.g. late events
+    pub(crate) watermark: i64,
+}
+
+/// Where the values of a group at a point in time come from.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct JoinSource {
+    /// imports of a batch group, or snapshots of a stream group
+    pub(crate) revisions: Vec<RevisionRange>,
+    /// the log of a stream group, replayed after its snapshot
+    pub(crate) stream_table: Option<String>,
+}
+
+pub(crate) enum OfflineStore {
+    Sqlite(sqlite::DB),
+}
+
+impl OfflineStore {
+    pub(crate) async fn open(opt: BackendOpt) -> Self {
+        if let Some(opt) = opt.sqlite {
+            let db = sqlite::DB::from(opt).await;
+            Self::Sqlite(db)
+        } else {
+            panic!("not backend found here!")
+        }
+    }
+
+    pub(crate) async fn close(&self) {
+        match self {
+            Self::Sqlite(db) => db.close().await,
+        }
+    }
+}
+
+impl OfflineStore {
+    /// Writes rows into a new data table, which is never changed afterwards.
+    /// Returns the number of imported rows.
+    pub(crate) async fn import<I, E>(
+        &self,
+        data_table: &str,
+        features: &[Feature],
+        rows: I,
+    ) -> Result<u64>
+    where
+        I: IntoIterator<Item = std::result::Result<Row, E>>,
+        E: Display,
+    {
+        match self {
+            Self::Sqlite(db) => db.import(data_table, features, rows).await,
+        }
+    }
+
+    /// Drops data tables, stream logs or snapshot tables, skipping the missing ones.
+    pub(crate) async fn drop_tables(&self, tables: &[String]) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.drop_tables(tables).await,
+        }
+    }
+
+    /// Drops the column of a feature from the tables having it, so the data of a
+    /// deleted feature never shows up again for a new feature of the same name.
+    pub(crate) async fn drop_column(&self, tables: &[String], feature_name: &str) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.drop_column(tables, feature_name).await,
+        }
+    }
+
+    /// Makes sure the log of a stream group exists and has a column for every feature.
+    pub(crate) async fn create_stream_table(
+        &self,
+        stream_table: &str,
+        features: &[Feature],
+    ) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.create_stream_table(stream_table, features).await,
+        }
+    }
+
+    /// Appends a row with event time `unix_milli` to the log of a stream group.
+    pub(crate) async fn push(
+        &self,
+        stream_table: &str,
+        features: &[Feature],
+        row: Row,
+        unix_milli: i64,
+    ) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.push(stream_table, features, row, unix_milli).await,
+        }
+    }
+
+    /// The latest event time in the log of a stream group, `None` if nothing is pushed.
+    pub(crate) async fn max_unix_milli(&self, stream_table: &str) -> Result<Option<i64>> {
+        match self {
+            Self::Sqlite(db) => db.max_unix_milli(stream_table).await,
+        }
+    }
+
+    /// The earliest event time after `unix_milli` in the log of a stream group.
+    pub(crate) async fn next_unix_milli(
+        &self,
+        stream_table: &str,
+        unix_milli: i64,
+    ) -> Result<Option<i64>> {
+        match self {
+            Self::Sqlite(db) => db.next_unix_milli(stream_table, unix_milli).await,
+        }
+    }
+
+    /// Compacts the log of a stream group into a new snapshot table, which holds the
+    /// latest row of each entity key as of `unix_milli`, by replaying the rows pushed
+    /// since the `base` snapshot onto it. Returns the number of rows in the snapshot
+    /// and its watermark.
+    pub(crate) async fn snapshot(
+        &self,
+        stream_table: &str,
+        base: Option<SnapshotBase<'_>>,
+        snapshot_table: &str,
+        features: &[Feature],
+        unix_milli: i64,
+    ) -> Result<(u64, i64)> {
+        match self {
+            Self::Sqlite(db) => {
+                db.snapshot(stream_table, base, snapshot_table, features, unix_milli)
+                    .await
+            }
+        }
+    }
+
+    /// Streams all rows of a data table, ordered by entity key. Features which
+    /// are not in the table, e.g. created after the import, are `None`.
+    pub(crate) fn export(
+        &self,
+        data_table: String,
+        features: Vec<Feature>,
+    ) -> BoxStream<'static, Result<Row>> {
+        match self {
+            Self::Sqlite(db) => db.export(data_table, features),
+        }
+    }
+
+    /// Streams the latest row of each entity key in a stream group as of `unix_milli`,
+    /// replaying the log onto `base`, the latest snapshot at that time, if any.
+    /// Ordered by entity key.
+    pub(crate) async fn export_stream(
+        &self,
+        stream_table: &str,
+        base: Option<SnapshotBase<'_>>,
+        features: &[Feature],
+        unix_milli: i64,
+    ) -> Result<BoxStream<'static, Result<Row>>> {
+        match self {
+            Self::Sqlite(db) => {
+                db.export_stream(stream_table, base, features, unix_milli)
+                    .await
+            }
+        }
+    }
+
+    /// Joins features to entity rows by point-in-time. `sources` holds the source of
+    /// every group in `features`. Each joined row is the entity key, the unix
+    /// milliseconds, the values of the entity row and then the values of `features`.
+    pub(crate) async fn join<I, E>(
+        &self,
+        features: &[Feature],
+        sources: &BTreeMap<i64, JoinSource>,
+        value_count: usize,
+        entity_rows: I,
+    ) -> Result<BoxStream<'static, Result<Vec<Option<FeatureValue>>>>>
+    where
+        I: IntoIterator<Item = std::result::Result<EntityRow, E>>,
+        E: Display,
+    {
+        match self {
+            Self::Sqlite(db) => db.join(features, sources, value_count, entity_rows).await,
+        }
+    }
+}
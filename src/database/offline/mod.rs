@@ -1 +1,4 @@
+mod db;
+mod sqlite;
 
+pub(crate) use db::{JoinSource, OfflineStore, RevisionRange, SnapshotBase};
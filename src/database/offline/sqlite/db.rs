@@ -0,0 +1,1534 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use async_stream::try_stream;
+use futures::stream::BoxStream;
+use futures::{Stream, TryStreamExt};
+use itertools::Itertools;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Connection, Row as _, SqlitePool};
+
+use crate::database::metadata::{Feature, FeatureValue};
+use crate::database::offline::{JoinSource, SnapshotBase};
+use crate::database::util::quote;
+use crate::database::{Error, Result, SQLiteOpt};
+use crate::feastore::{EntityRow, Row};
+
+pub struct DB {
+    pool: SqlitePool,
+}
+
+impl DB {
+    pub(crate) async fn from(db_file: SQLiteOpt) -> Self {
+        let pool = SqlitePool::connect(format!("sqlite://{}", &db_file.db_file).as_str())
+            .await
+            .unwrap_or_else(|_| panic!("open {} failed!", db_file.db_file));
+
+        Self { pool }
+    }
+
+    pub(crate) async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    pub(crate) async fn import<I, E>(
+        &self,
+        data_table: &str,
+        features: &[Feature],
+        rows: I,
+    ) -> Result<u64>
+    where
+        I: IntoIterator<Item = std::result::Result<Row, E>>,
+        E: Display,
+    {
+        let mut tx = self.pool.begin().await?;
+        let count = import(&mut *tx, data_table, features, rows).await?;
+        tx.commit().await?;
+        Ok(count)
+    }
+
+    pub(crate) async fn drop_tables(&self, tables: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for table in tables {
+            let query = format!("DROP TABLE IF EXISTS {}", quote(table));
+            sqlx::query(&query).execute(&mut *tx).await?;
+        }
+        tx.commit().await.map_err(|e| e.into())
+    }
+
+    pub(crate) async fn drop_column(&self, tables: &[String], feature_name: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for table in tables {
+            // a missing table has no columns
+            if table_columns(&mut tx, table)
+                .await?
+                .iter()
+                .any(|c| c == feature_name)
+            {
+                let query = format!(
+                    "ALTER TABLE {} DROP COLUMN {}",
+                    quote(table),
+                    quote(feature_name)
+                );
+                sqlx::query(&query).execute(&mut *tx).await?;
+            }
+        }
+        tx.commit().await.map_err(|e| e.into())
+    }
+
+    pub(crate) async fn create_stream_table(
+        &self,
+        stream_table: &str,
+        features: &[Feature],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        create_stream_table(&mut tx, stream_table, features).await?;
+        tx.commit().await.map_err(|e| e.into())
+    }
+
+    pub(crate) async fn push(
+        &self,
+        stream_table: &str,
+        features: &[Feature],
+        row: Row,
+        unix_milli: i64,
+    ) -> Result<()> {
+        push(
+            &mut *self.pool.acquire().await?,
+            stream_table,
+            features,
+            row,
+            unix_milli,
+        )
+        .await
+    }
+
+    pub(crate) async fn max_unix_milli(&self, stream_table: &str) -> Result<Option<i64>> {
+        max_unix_milli(&self.pool, stream_table).await
+    }
+
+    pub(crate) async fn next_unix_milli(
+        &self,
+        stream_table: &str,
+        unix_milli: i64,
+    ) -> Result<Option<i64>> {
+        next_unix_milli(&self.pool, stream_table, unix_milli).await
+    }
+
+    pub(crate) async fn snapshot(
+        &self,
+        stream_table: &str,
+        base: Option<SnapshotBase<'_>>,
+        snapshot_table: &str,
+        features: &[Feature],
+        unix_milli: i64,
+    ) -> Result<(u64, i64)> {
+        let mut tx = self.pool.begin().await?;
+        let res = snapshot(
+            &mut *tx,
+            stream_table,
+            base,
+            snapshot_table,
+            features,
+            unix_milli,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(res)
+    }
+
+    pub(crate) fn export(
+        &self,
+        data_table: String,
+        features: Vec<Feature>,
+    ) -> BoxStream<'static, Result<Row>> {
+        Box::pin(export(self.pool.clone(), data_table, features))
+    }
+
+    pub(crate) async fn export_stream(
+        &self,
+        stream_table: &str,
+        base: Option<SnapshotBase<'_>>,
+        features: &[Feature],
+        unix_milli: i64,
+    ) -> Result<BoxStream<'static, Result<Row>>> {
+        // nothing is pushed yet, and so there is no snapshot either
+        if max_unix_milli(&self.pool, stream_table).await?.is_none() {
+            return Ok(Box::pin(futures::stream::empty()));
+        }
+
+        let latest = latest_rows(
+            &self.pool,
+            stream_table,
+            base.map(|b| b.data_table),
+            features,
+        )
+        .await?;
+        Ok(Box::pin(export_latest(
+            self.pool.clone(),
+            format!("{latest} ORDER BY entity_key"),
+            [
+                base.map_or(0, |b| b.watermark),
+                base.map_or(i64::MIN, |b| b.unix_milli),
+                unix_milli,
+                i64::MAX,
+            ],
+            features.to_vec(),
+        )))
+    }
+
+    pub(crate) async fn join<I, E>(
+        &self,
+        features: &[Feature],
+        sources: &BTreeMap<i64, JoinSource>,
+        value_count: usize,
+        entity_rows: I,
+    ) -> Result<BoxStream<'static, Result<Vec<Option<FeatureValue>>>>>
+    where
+        I: IntoIterator<Item = std::result::Result<EntityRow, E>>,
+        E: Display,
+    {
+        // temporary tables live in one connection, which is closed once the stream is dropped
+        let mut conn = self.pool.acquire().await?.detach();
+
+        let mut tx = conn.begin().await?;
+        prepare_join(&mut tx, features, sources, value_count, entity_rows).await?;
+        tx.commit().await?;
+
+        let group_ids = sources.keys().copied().collect();
+        Ok(Box::pin(fetch_joined(
+            conn,
+            features.to_vec(),
+            group_ids,
+            value_count,
+        )))
+    }
+}
+
+async fn import<'a, A, I, E>(
+    conn: A,
+    data_table: &str,
+    features: &[Feature],
+    rows: I,
+) -> Result<u64>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+    I: IntoIterator<Item = std::result::Result<Row, E>>,
+    E: Display,
+{
+    let mut conn = conn.acquire().await?;
+    let table = quote(data_table);
+
+    let columns = features
+        .iter()
+        .map(|f| format!(", {} {}", quote(&f.name), f.value_type.sqlite_type()))
+        .join("");
+    let query = format!("CREATE TABLE {table} (entity_key TEXT NOT NULL PRIMARY KEY{columns})");
+    match sqlx::query(&query).execute(&mut *conn).await {
+        Err(sqlx::Error::Database(e)) if e.message().ends_with("already exists") => {
+            return Err(Error::ColumnAlreadyExist(data_table.to_owned()));
+        }
+        res => res?,
+    };
+
+    let query = format!(
+        "INSERT INTO {table} (entity_key{}) VALUES (?{})",
+        features
+            .iter()
+            .map(|f| format!(", {}", quote(&f.name)))
+            .join(""),
+        ", ?".repeat(features.len())
+    );
+
+    let mut count = 0;
+    for row in rows {
+        let row = row.map_err(|e| Error::Other(e.to_string()))?;
+        if row.values.len() != features.len() {
+            return Err(format!(
+                "expected {} values for entity key '{}', got {}",
+                features.len(),
+                row.entity_key,
+                row.values.len()
+            )
+            .into());
+        }
+
+        let mut query = sqlx::query(&query).bind(row.entity_key);
+        for value in row.values {
+            query = query.bind(value);
+        }
+        query.execute(&mut *conn).await?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Creates a stream log, or extends it with new features.
+async fn create_stream_table(
+    conn: &mut SqliteConnection,
+    stream_table: &str,
+    features: &[Feature],
+) -> Result<()> {
+    let table = quote(stream_table);
+
+    let query = format!(
+        "CREATE TABLE IF NOT EXISTS {table} (entity_key TEXT NOT NULL, unix_milli INTEGER NOT NULL)"
+    );
+    sqlx::query(&query).execute(&mut *conn).await?;
+    let query = format!(
+        "CREATE INDEX IF NOT EXISTS {} ON {table} (unix_milli)",
+        quote(&format!("{stream_table}_unix_milli"))
+    );
+    sqlx::query(&query).execute(&mut *conn).await?;
+    let query = format!(
+        "CREATE INDEX IF NOT EXISTS {} ON {table} (entity_key, unix_milli)",
+        quote(&format!("{stream_table}_entity_key"))
+    );
+    sqlx::query(&query).execute(&mut *conn).await?;
+
+    let columns = table_columns(&mut *conn, stream_table).await?;
+    for feature in features {
+        if columns.contains(&feature.name) {
+            continue;
+        }
+
+        let query = format!(
+            "ALTER TABLE {table} ADD COLUMN {} {}",
+            quote(&feature.name),
+            feature.value_type.sqlite_type()
+        );
+        sqlx::query(&query).execute(&mut *conn).await?;
+    }
+
+    Ok(())
+}
+
+/// Appends a row to a stream log, created by `create_stream_table`.
+// takes a connection rather than `sqlx::Acquire`, which keeps `Store::push` Send to serve
+async fn push(
+    conn: &mut SqliteConnection,
+    stream_table: &str,
+    features: &[Feature],
+    row: Row,
+    unix_milli: i64,
+) -> Result<()> {
+    let query = format!(
+        "INSERT INTO {} (entity_key, unix_milli{}) VALUES (?, ?{})",
+        quote(stream_table),
+        features
+            .iter()
+            .map(|f| format!(", {}", quote(&f.name)))
+            .join(""),
+        ", ?".repeat(features.len())
+    );
+    let mut query = sqlx::query(&query).bind(row.entity_key).bind(unix_milli);
+    for value in row.values {
+        query = query.bind(value);
+    }
+    query.execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+async fn next_unix_milli<'a, A>(conn: A, stream_table: &str, unix_milli: i64) -> Result<Option<i64>>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+    if table_columns(&mut conn, stream_table).await?.is_empty() {
+        return Ok(None);
+    }
+
+    let query = format!(
+        "SELECT MIN(unix_milli) FROM {} WHERE unix_milli > ?",
+        quote(stream_table)
+    );
+    sqlx::query_scalar(&query)
+        .bind(unix_milli)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| e.into())
+}
+
+async fn max_unix_milli<'a, A>(conn: A, stream_table: &str) -> Result<Option<i64>>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(stream_table)
+        .fetch_optional(&mut *conn)
+        .await?
+        .is_some();
+    if !exists {
+        return Ok(None);
+    }
+
+    let query = format!("SELECT MAX(unix_milli) FROM {}", quote(stream_table));
+    sqlx::query_scalar(&query)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| e.into())
+}
+
+/// Creates a snapshot table with the latest row of each entity key as of `unix_milli`
+/// (inclusive), by merging the `base` snapshot with the rows pushed to the stream log
+/// since it. Returns the number of rows and the watermark, the rowid of the last row
+/// pushed, as the rows pushed meanwhile wait for the next snapshot.
+async fn snapshot<'a, A>(
+    conn: A,
+    stream_table: &str,
+    base: Option<SnapshotBase<'_>>,
+    snapshot_table: &str,
+    features: &[Feature],
+    unix_milli: i64,
+) -> Result<(u64, i64)>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+    let table = quote(snapshot_table);
+
+    let query = format!(
+        "CREATE TABLE {table} (entity_key TEXT NOT NULL PRIMARY KEY, unix_milli INTEGER NOT NULL{})",
+        features
+            .iter()
+            .map(|f| format!(", {} {}", quote(&f.name), f.value_type.sqlite_type()))
+            .join("")
+    );
+    match sqlx::query(&query).execute(&mut *conn).await {
+        Err(sqlx::Error::Database(e)) if e.message().ends_with("already exists") => {
+            return Err(Error::ColumnAlreadyExist(snapshot_table.to_owned()));
+        }
+        res => res?,
+    };
+
+    // read after the write above, which keeps other connections from pushing meanwhile
+    let query = format!(
+        "SELECT COALESCE(MAX(rowid), 0) FROM {}",
+        quote(stream_table)
+    );
+    let watermark: i64 = sqlx::query_scalar(&query).fetch_one(&mut *conn).await?;
+
+    let names = features
+        .iter()
+        .map(|f| format!(", {}", quote(&f.name)))
+        .join("");
+    let latest = latest_rows(
+        &mut *conn,
+        stream_table,
+        base.map(|b| b.data_table),
+        features,
+    )
+    .await?;
+    let query = format!("INSERT INTO {table} (entity_key, unix_milli{names}) {latest}");
+    let res = sqlx::query(&query)
+        .bind(base.map_or(0, |b| b.watermark))
+        .bind(base.map_or(i64::MIN, |b| b.unix_milli))
+        .bind(unix_milli)
+        .bind(watermark)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok((res.rows_affected(), watermark))
+}
+
+/// Builds the query selecting the latest row of each entity key as of an event time
+/// from the `base` snapshot table and the log rows not merged into it, i.e. pushed
+/// after its watermark or later than its time. It binds the watermark and the time of
+/// the base, the event time and the last rowid to read, in this order. Rows of the
+/// same event time are ordered by when they were pushed.
+async fn latest_rows<'a, A>(
+    conn: A,
+    stream_table: &str,
+    base: Option<&str>,
+    features: &[Feature],
+) -> Result<String>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+
+    let select = |table: &str, columns: &[String], seq: &str| {
+        format!(
+            "SELECT entity_key, unix_milli{}, {seq} AS seq FROM {}",
+            features
+                .iter()
+                .map(|f| format!(
+                    ", {} AS {}",
+                    column_or_null(columns, &f.name, ""),
+                    quote(&f.name)
+                ))
+                .join(""),
+            quote(table)
+        )
+    };
+
+    let log_columns = table_columns(&mut conn, stream_table).await?;
+    let mut rows = format!(
+        "{} WHERE (rowid > ? OR unix_milli > ?) AND unix_milli <= ? AND rowid <= ?",
+        select(stream_table, &log_columns, "rowid")
+    );
+    if let Some(base_table) = base {
+        let base_columns = table_columns(&mut conn, base_table).await?;
+        rows = format!(
+            "{} UNION ALL {rows}",
+            select(base_table, &base_columns, "0")
+        );
+    }
+
+    Ok(format!(
+        "SELECT entity_key, unix_milli{} FROM (SELECT *, ROW_NUMBER() OVER (PARTITION BY entity_key ORDER BY unix_milli DESC, seq DESC) AS rn FROM ({rows})) WHERE rn = 1",
+        features
+            .iter()
+            .map(|f| format!(", {}", quote(&f.name)))
+            .join("")
+    ))
+}
+
+fn export(
+    pool: SqlitePool,
+    data_table: String,
+    features: Vec<Feature>,
+) -> impl Stream<Item = Result<Row>> {
+    try_stream! {
+        let table = quote(&data_table);
+        let columns = table_columns(&mut *pool.acquire().await?, &data_table).await?;
+
+        let query = format!(
+            "SELECT entity_key{} FROM {table} ORDER BY entity_key",
+            features
+                .iter()
+                .map(|f| format!(", {}", column_or_null(&columns, &f.name, "")))
+                .join("")
+        );
+
+        let mut rows = sqlx::query(&query).fetch(&pool);
+        while let Some(row) = rows.try_next().await? {
+            yield decode_row(&row, &features, 1)?;
+        }
+    }
+}
+
+fn export_latest(
+    pool: SqlitePool,
+    query: String,
+    binds: [i64; 4],
+    features: Vec<Feature>,
+) -> impl Stream<Item = Result<Row>> {
+    try_stream! {
+        let mut query = sqlx::query(&query);
+        for bind in binds {
+            query = query.bind(bind);
+        }
+        let mut rows = query.fetch(&pool);
+        while let Some(row) = rows.try_next().await? {
+            yield decode_row(&row, &features, 2)?;
+        }
+    }
+}
+
+/// Decodes the entity key in the first column and the values of `features`
+/// from column `offset` on.
+fn decode_row(row: &sqlx::sqlite::SqliteRow, features: &[Feature], offset: usize) -> Result<Row> {
+    let values = features
+        .iter()
+        .enumerate()
+        .map(|(i, f)| FeatureValue::from_sqlite_row(row, i + offset, &f.value_type))
+        .collect::<std::result::Result<Vec<_>, sqlx::Error>>()?;
+    Ok(Row {
+        entity_key: row.try_get(0)?,
+        values,
+    })
+}
+
+/// Loads entity rows into the temporary table `entity_rows`, and for each group
+/// the values valid at the time of each entity row into `joined_{group_id}`.
+async fn prepare_join<I, E>(
+    conn: &mut SqliteConnection,
+    features: &[Feature],
+    sources: &BTreeMap<i64, JoinSource>,
+    value_count: usize,
+    entity_rows: I,
+) -> Result<()>
+where
+    I: IntoIterator<Item = std::result::Result<EntityRow, E>>,
+    E: Display,
+{
+    let query = format!(
+        "CREATE TEMP TABLE entity_rows (id INTEGER NOT NULL PRIMARY KEY, entity_key TEXT NOT NULL, unix_milli INTEGER NOT NULL{})",
+        (0..value_count).map(|i| format!(", value_{i} TEXT")).join("")
+    );
+    sqlx::query(&query).execute(&mut *conn).await?;
+
+    let query = format!(
+        "INSERT INTO entity_rows (id, entity_key, unix_milli{}) VALUES (?, ?, ?{})",
+        (0..value_count).map(|i| format!(", value_{i}")).join(""),
+        ", ?".repeat(value_count)
+    );
+    for (id, row) in entity_rows.into_iter().enumerate() {
+        let row = row.map_err(|e| Error::Other(e.to_string()))?;
+        if row.values.len() != value_count {
+            return Err(format!(
+                "expected {} values for entity key '{}', got {}",
+                value_count,
+                row.entity_key,
+                row.values.len()
+            )
+            .into());
+        }
+
+        let mut query = sqlx::query(&query)
+            .bind(id as i64)
+            .bind(row.entity_key)
+            .bind(row.unix_milli);
+        for value in row.values {
+            query = query.bind(value);
+        }
+        query.execute(&mut *conn).await?;
+    }
+    sqlx::query("CREATE INDEX temp.entity_rows_unix_milli ON entity_rows (unix_milli)")
+        .execute(&mut *conn)
+        .await?;
+
+    for (group_id, source) in sources {
+        let positions: Vec<usize> = features
+            .iter()
+            .positions(|f| f.group_id == *group_id)
+            .collect();
+
+        let joined = format!("joined_{group_id}");
+        let query = format!(
+            "CREATE TEMP TABLE {joined} (id INTEGER NOT NULL PRIMARY KEY{})",
+            positions
+                .iter()
+                .map(|&p| format!(", f_{p} {}", features[p].value_type.sqlite_type()))
+                .join("")
+        );
+        sqlx::query(&query).execute(&mut *conn).await?;
+
+        let targets = positions.iter().map(|p| format!(", f_{p}")).join("");
+        let select = |columns: &[String]| {
+            positions
+                .iter()
+                .map(|&p| {
+                    format!(
+                        ", {} AS f_{p}",
+                        column_or_null(columns, &features[p].name, "")
+                    )
+                })
+                .join("")
+        };
+
+        let stream_table = match source.stream_table {
+            Some(ref stream_table) => stream_table,
+            None => {
+                for range in &source.revisions {
+                    let columns = table_columns(conn, &range.data_table).await?;
+                    let query = format!(
+                        "INSERT INTO {joined} (id{targets}) SELECT e.id{} FROM entity_rows AS e JOIN {} AS d ON e.entity_key = d.entity_key WHERE e.unix_milli >= ? AND e.unix_milli < ?",
+                        select(&columns),
+                        quote(&range.data_table)
+                    );
+                    sqlx::query(&query)
+                        .bind(range.start)
+                        .bind(range.end)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+                continue;
+            }
+        };
+
+        // a stream group starts from the latest snapshot at or before the entity row,
+        // or from the beginning of the log, and replays the log up to the entity row:
+        // the rows after the snapshot, and the ones pushed after it was taken
+        let log_columns = table_columns(conn, stream_table).await?;
+        let first = source.revisions.first().map_or(i64::MAX, |r| r.start);
+        let ranges = std::iter::once((None, i64::MIN, first, 0)).chain(
+            source
+                .revisions
+                .iter()
+                .map(|r| (Some(r.data_table.as_str()), r.start, r.end, r.watermark)),
+        );
+        for (snapshot, start, end, watermark) in ranges {
+            let mut candidates = Vec::new();
+            if let Some(snapshot) = snapshot {
+                let columns = table_columns(conn, snapshot).await?;
+                candidates.push(format!(
+                    "SELECT entity_key, unix_milli{}, 0 AS seq FROM {}",
+                    select(&columns),
+                    quote(snapshot)
+                ));
+            }
+            // a missing log means nothing was pushed
+            if !log_columns.is_empty() {
+                candidates.push(replay_rows(
+                    stream_table,
+                    &select(&log_columns),
+                    snapshot.is_some(),
+                ));
+            }
+            if candidates.is_empty() {
+                continue;
+            }
+
+            let query = format!(
+                "INSERT INTO {joined} (id{targets}) SELECT id{targets} FROM (SELECT e.id{}, ROW_NUMBER() OVER (PARTITION BY e.id ORDER BY c.unix_milli DESC, c.seq DESC) AS rn FROM entity_rows AS e JOIN ({}) AS c ON e.entity_key = c.entity_key AND c.unix_milli <= e.unix_milli WHERE e.unix_milli >= ? AND e.unix_milli < ?) WHERE rn = 1",
+                positions.iter().map(|p| format!(", c.f_{p}")).join(""),
+                candidates.join(" UNION ALL ")
+            );
+            let mut query = sqlx::query(&query);
+            if !log_columns.is_empty() {
+                if snapshot.is_some() {
+                    query = query.bind(watermark).bind(start);
+                }
+                query = query.bind(end);
+            }
+            query.bind(start).bind(end).execute(&mut *conn).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a query of the log rows to replay for a join range ending before `end`:
+/// the whole log before it, or after a snapshot only the rows pushed after its
+/// watermark or later than its time. It binds the watermark and the time of the
+/// snapshot if there is one, and the end of the range, in this order.
+fn replay_rows(stream_table: &str, columns: &str, after_snapshot: bool) -> String {
+    format!(
+        "SELECT entity_key, unix_milli{columns}, rowid AS seq FROM {} WHERE {}unix_milli < ?",
+        quote(stream_table),
+        if after_snapshot {
+            "(rowid > ? OR unix_milli > ?) AND "
+        } else {
+            ""
+        }
+    )
+}
+
+fn fetch_joined(
+    mut conn: SqliteConnection,
+    features: Vec<Feature>,
+    group_ids: Vec<i64>,
+    value_count: usize,
+) -> impl Stream<Item = Result<Vec<Option<FeatureValue>>>> {
+    try_stream! {
+        let query = format!(
+            "SELECT e.entity_key, e.unix_milli{}{} FROM entity_rows AS e{} ORDER BY e.id",
+            (0..value_count).map(|i| format!(", e.value_{i}")).join(""),
+            features
+                .iter()
+                .enumerate()
+                .map(|(p, f)| format!(", j{}.f_{p}", f.group_id))
+                .join(""),
+            group_ids
+                .iter()
+                .map(|id| format!(" LEFT JOIN joined_{id} AS j{id} ON e.id = j{id}.id"))
+                .join("")
+        );
+
+        let mut rows = sqlx::query(&query).fetch(&mut conn);
+        while let Some(row) = rows.try_next().await? {
+            let mut values = Vec::with_capacity(2 + value_count + features.len());
+            values.push(Some(FeatureValue::String(row.try_get(0)?)));
+            values.push(Some(FeatureValue::Int64(row.try_get(1)?)));
+            for i in 0..value_count {
+                values.push(row.try_get::<Option<String>, _>(2 + i)?.map(FeatureValue::String));
+            }
+            for (i, f) in features.iter().enumerate() {
+                values.push(FeatureValue::from_sqlite_row(&row, 2 + value_count + i, &f.value_type)?);
+            }
+            yield values;
+        }
+    }
+}
+
+async fn table_columns(conn: &mut SqliteConnection, table: &str) -> Result<Vec<String>> {
+    sqlx::query(&format!("PRAGMA table_info({})", quote(table)))
+        .fetch_all(conn)
+        .await?
+        .iter()
+        .map(|row| row.try_get::<String, _>("name"))
+        .collect::<std::result::Result<Vec<String>, sqlx::Error>>()
+        .map_err(|e| e.into())
+}
+
+/// Selects the column if the table has it, otherwise NULL, e.g. for features
+/// created after the data table.
+fn column_or_null(columns: &[String], name: &str, prefix: &str) -> String {
+    match columns.iter().any(|c| c == name) {
+        true => format!("{prefix}{}", quote(name)),
+        false => "NULL".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::database::offline::RevisionRange;
+
+    use chrono::Utc;
+
+    use crate::database::metadata::ValueType;
+
+    fn feature(name: &str, value_type: ValueType) -> Feature {
+        Feature {
+            id: 0,
+            name: name.to_owned(),
+            group_id: 1,
+            value_type,
+            description: String::new(),
+            create_time: Utc::now(),
+            modify_time: Utc::now(),
+            group: None,
+        }
+    }
+
+    fn row(
+        entity_key: &str,
+        values: Vec<Option<FeatureValue>>,
+    ) -> std::result::Result<Row, String> {
+        Ok(Row {
+            entity_key: entity_key.to_owned(),
+            values,
+        })
+    }
+
+    #[sqlx::test]
+    async fn import(pool: SqlitePool) {
+        let features = vec![
+            feature("model", ValueType::StringType),
+            feature("price", ValueType::Int64),
+        ];
+        let rows = vec![
+            row(
+                "1",
+                vec![
+                    Some(FeatureValue::String("xiaomi".to_owned())),
+                    Some(FeatureValue::Int64(1999)),
+                ],
+            ),
+            row(
+                "2",
+                vec![Some(FeatureValue::String("apple".to_owned())), None],
+            ),
+        ];
+
+        let res = super::import(&pool, "offline_batch_1_1", &features, rows.clone()).await;
+        assert!(res.is_ok_and(|count| count == 2));
+
+        let values: Vec<(String, String, Option<i64>)> = sqlx::query(
+            "SELECT entity_key, model, price FROM offline_batch_1_1 ORDER BY entity_key",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| (r.get(0), r.get(1), r.get(2)))
+        .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("1".to_owned(), "xiaomi".to_owned(), Some(1999)),
+                ("2".to_owned(), "apple".to_owned(), None),
+            ]
+        );
+
+        // a revision table is immutable
+        let res = super::import(&pool, "offline_batch_1_1", &features, rows).await;
+        assert!(res.is_err_and(|e| match e {
+            Error::ColumnAlreadyExist(table) => table == "offline_batch_1_1",
+            _ => false,
+        }));
+    }
+
+    #[sqlx::test]
+    async fn import_invalid_rows(pool: SqlitePool) {
+        let features = vec![feature("price", ValueType::Int64)];
+
+        let rows = vec![row("1", vec![])];
+        let res = super::import(&pool, "offline_batch_1_1", &features, rows).await;
+        assert!(res.is_err_and(
+            |e| e == Error::Other("expected 1 values for entity key '1', got 0".to_owned())
+        ));
+
+        let rows = vec![
+            row("1", vec![Some(FeatureValue::Int64(1))]),
+            Err("invalid value".to_owned()),
+        ];
+        let res = super::import(&pool, "offline_batch_1_2", &features, rows).await;
+        assert!(res.is_err_and(|e| e == Error::Other("invalid value".to_owned())));
+    }
+
+    #[sqlx::test]
+    async fn drop_tables_and_column(pool: SqlitePool) {
+        let db = DB { pool };
+        let features = vec![
+            feature("model", ValueType::StringType),
+            feature("price", ValueType::Int64),
+        ];
+        let rows = vec![row(
+            "1",
+            vec![
+                Some(FeatureValue::String("xiaomi".to_owned())),
+                Some(FeatureValue::Int64(1999)),
+            ],
+        )];
+        for table in ["offline_batch_1_1", "offline_batch_1_2"] {
+            super::import(&db.pool, table, &features, rows.clone())
+                .await
+                .unwrap();
+        }
+        let tables = [
+            "offline_batch_1_1".to_owned(),
+            "offline_batch_1_2".to_owned(),
+            "offline_stream_1".to_owned(),
+        ];
+
+        // missing tables are skipped
+        db.drop_column(&tables, "price").await.unwrap();
+        for table in &tables[..2] {
+            let columns = table_columns(&mut db.pool.acquire().await.unwrap(), table)
+                .await
+                .unwrap();
+            assert_eq!(columns, vec!["entity_key", "model"]);
+        }
+
+        db.drop_tables(&tables).await.unwrap();
+        for table in &tables {
+            let columns = table_columns(&mut db.pool.acquire().await.unwrap(), table)
+                .await
+                .unwrap();
+            assert!(columns.is_empty());
+        }
+    }
+
+    #[sqlx::test]
+    async fn export(pool: SqlitePool) {
+        let mut features = vec![
+            feature("model", ValueType::StringType),
+            feature("price", ValueType::Int64),
+        ];
+        let rows = vec![
+            row(
+                "2",
+                vec![Some(FeatureValue::String("apple".to_owned())), None],
+            ),
+            row(
+                "1",
+                vec![
+                    Some(FeatureValue::String("xiaomi".to_owned())),
+                    Some(FeatureValue::Int64(1999)),
+                ],
+            ),
+        ];
+        super::import(&pool, "offline_batch_1_1", &features, rows)
+            .await
+            .unwrap();
+
+        // a feature created after the import is exported as null
+        features.insert(0, feature("is_new", ValueType::Bool));
+        let rows: Vec<Row> =
+            super::export(pool, "offline_batch_1_1".to_owned(), features[..2].to_vec())
+                .try_collect()
+                .await
+                .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    entity_key: "1".to_owned(),
+                    values: vec![None, Some(FeatureValue::String("xiaomi".to_owned()))],
+                },
+                Row {
+                    entity_key: "2".to_owned(),
+                    values: vec![None, Some(FeatureValue::String("apple".to_owned()))],
+                },
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn export_stream(pool: SqlitePool) {
+        let db = DB { pool };
+        let features = vec![feature("clicks", ValueType::Int64)];
+        let click_row = |key: &str, clicks: i64| Row {
+            entity_key: key.to_owned(),
+            values: vec![Some(FeatureValue::Int64(clicks))],
+        };
+        let export = |base: Option<SnapshotBase<'static>>, unix_milli: i64| {
+            let features = features.clone();
+            let db = &db;
+            async move {
+                db.export_stream("offline_stream_1", base, &features, unix_milli)
+                    .await
+                    .unwrap()
+                    .try_collect::<Vec<Row>>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // nothing is pushed yet
+        assert_eq!(export(None, 1000).await, vec![]);
+        db.create_stream_table("offline_stream_1", &features)
+            .await
+            .unwrap();
+        assert_eq!(export(None, 1000).await, vec![]);
+
+        for (key, clicks, unix_milli) in
+            [("2", 1, 100), ("1", 1, 200), ("1", 2, 1100), ("2", 2, 1500)]
+        {
+            super::push(
+                &mut db.pool.acquire().await.unwrap(),
+                "offline_stream_1",
+                &features,
+                click_row(key, clicks),
+                unix_milli,
+            )
+            .await
+            .unwrap();
+        }
+        assert_eq!(
+            export(None, 1100).await,
+            vec![click_row("1", 2), click_row("2", 1)]
+        );
+
+        // the log after the snapshot is replayed onto it
+        super::snapshot(
+            &db.pool,
+            "offline_stream_1",
+            None,
+            "s_1000",
+            &features,
+            1000,
+        )
+        .await
+        .unwrap();
+        sqlx::query("DELETE FROM offline_stream_1 WHERE unix_milli <= 1000")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let base = Some(SnapshotBase {
+            data_table: "s_1000",
+            unix_milli: 1000,
+            watermark: 4,
+        });
+        assert_eq!(
+            export(base, 1000).await,
+            vec![click_row("1", 1), click_row("2", 1)]
+        );
+        assert_eq!(
+            export(base, 2000).await,
+            vec![click_row("1", 2), click_row("2", 2)]
+        );
+
+        // so is a late event pushed after the snapshot was taken
+        super::push(
+            &mut db.pool.acquire().await.unwrap(),
+            "offline_stream_1",
+            &features,
+            click_row("2", 5),
+            900,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            export(base, 1000).await,
+            vec![click_row("1", 1), click_row("2", 5)]
+        );
+    }
+
+    #[sqlx::test]
+    async fn join(pool: SqlitePool) {
+        let db = DB { pool };
+        let mut price = feature("price", ValueType::Int64);
+        let model = feature("model", ValueType::StringType);
+        let mut age = feature("age", ValueType::Int64);
+        price.group_id = 1;
+        age.group_id = 2;
+
+        let price_row = |key: &str, price: i64| row(key, vec![Some(FeatureValue::Int64(price))]);
+        let rows = vec![price_row("1", 10), price_row("2", 20)];
+        super::import(&db.pool, "offline_batch_1_10", &[price.clone()], rows)
+            .await
+            .unwrap();
+        let rows = vec![price_row("1", 11)];
+        super::import(&db.pool, "offline_batch_1_20", &[price.clone()], rows)
+            .await
+            .unwrap();
+        let rows = vec![price_row("1", 18)];
+        super::import(&db.pool, "offline_batch_2_15", &[age.clone()], rows)
+            .await
+            .unwrap();
+
+        let range = |data_table: &str, start: i64, end: i64| RevisionRange {
+            data_table: data_table.to_owned(),
+            start,
+            end,
+            watermark: 0,
+        };
+        let batch = |revisions| JoinSource {
+            revisions,
+            stream_table: None,
+        };
+        let sources = BTreeMap::from([
+            (
+                1,
+                batch(vec![
+                    range("offline_batch_1_10", 10, 20),
+                    range("offline_batch_1_20", 20, i64::MAX),
+                ]),
+            ),
+            (2, batch(vec![range("offline_batch_2_15", 15, i64::MAX)])),
+        ]);
+
+        let entity_row = |key: &str, unix_milli: i64| -> std::result::Result<EntityRow, String> {
+            Ok(EntityRow {
+                entity_key: key.to_owned(),
+                unix_milli,
+                values: vec![format!("label_{key}_{unix_milli}")],
+            })
+        };
+        let entity_rows = vec![
+            entity_row("1", 5),
+            entity_row("1", 10),
+            entity_row("1", 19),
+            entity_row("1", 25),
+            entity_row("2", 30),
+            entity_row("3", 30),
+        ];
+
+        // the model feature was created after all revisions
+        let features = vec![age, price, model];
+        let rows: Vec<Vec<Option<FeatureValue>>> = db
+            .join(&features, &sources, 1, entity_rows)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let int = |v: i64| Some(FeatureValue::Int64(v));
+        let joined = |key: &str, unix_milli: i64, age, price| {
+            vec![
+                Some(FeatureValue::String(key.to_owned())),
+                int(unix_milli),
+                Some(FeatureValue::String(format!("label_{key}_{unix_milli}"))),
+                age,
+                price,
+                None,
+            ]
+        };
+        assert_eq!(
+            rows,
+            vec![
+                joined("1", 5, None, None),
+                joined("1", 10, None, int(10)),
+                joined("1", 19, int(18), int(10)),
+                joined("1", 25, int(18), int(11)),
+                joined("2", 30, None, None),
+                joined("3", 30, None, None),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn push(pool: SqlitePool) {
+        let mut features = vec![feature("clicks", ValueType::Int64)];
+        let click_row = |clicks: i64| Row {
+            entity_key: "1".to_owned(),
+            values: vec![Some(FeatureValue::Int64(clicks))],
+        };
+        let mut conn = pool.acquire().await.unwrap();
+        super::create_stream_table(&mut conn, "offline_stream_1", &features)
+            .await
+            .unwrap();
+        super::push(&mut conn, "offline_stream_1", &features, click_row(1), 100)
+            .await
+            .unwrap();
+
+        // the log is extended with new features
+        features.push(feature("last_post", ValueType::StringType));
+        super::create_stream_table(&mut conn, "offline_stream_1", &features)
+            .await
+            .unwrap();
+        let mut row = click_row(2);
+        row.values
+            .push(Some(FeatureValue::String("hello".to_owned())));
+        super::push(
+            &mut pool.acquire().await.unwrap(),
+            "offline_stream_1",
+            &features,
+            row,
+            200,
+        )
+        .await
+        .unwrap();
+
+        let values: Vec<(String, i64, i64, Option<String>)> = sqlx::query(
+            "SELECT entity_key, unix_milli, clicks, last_post FROM offline_stream_1 ORDER BY unix_milli",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| (r.get(0), r.get(1), r.get(2), r.get(3)))
+        .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("1".to_owned(), 100, 1, None),
+                ("1".to_owned(), 200, 2, Some("hello".to_owned())),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn snapshot(pool: SqlitePool) {
+        let mut features = vec![feature("clicks", ValueType::Int64)];
+        let push = |key: &str, clicks: i64, unix_milli: i64, features: Vec<Feature>| {
+            let pool = pool.clone();
+            let row = Row {
+                entity_key: key.to_owned(),
+                values: features
+                    .iter()
+                    .map(|_| Some(FeatureValue::Int64(clicks)))
+                    .collect(),
+            };
+            async move {
+                let mut conn = pool.acquire().await.unwrap();
+                super::create_stream_table(&mut conn, "offline_stream_1", &features)
+                    .await
+                    .unwrap();
+                super::push(&mut conn, "offline_stream_1", &features, row, unix_milli)
+                    .await
+                    .unwrap()
+            }
+        };
+        let snapshot_rows = |table: &str| {
+            let query =
+                format!("SELECT entity_key, unix_milli, clicks FROM {table} ORDER BY entity_key");
+            let pool = pool.clone();
+            async move {
+                sqlx::query(&query)
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|r| (r.get(0), r.get(1), r.get(2)))
+                    .collect::<Vec<(String, i64, i64)>>()
+            }
+        };
+
+        assert_eq!(
+            super::max_unix_milli(&pool, "offline_stream_1").await,
+            Ok(None)
+        );
+
+        push("1", 1, 100, features.clone()).await;
+        push("1", 2, 200, features.clone()).await;
+        push("2", 1, 200, features.clone()).await;
+        push("2", 2, 200, features.clone()).await;
+        push("1", 3, 1100, features.clone()).await;
+        assert_eq!(
+            super::max_unix_milli(&pool, "offline_stream_1").await,
+            Ok(Some(1100))
+        );
+
+        let res = super::snapshot(&pool, "offline_stream_1", None, "s_1000", &features, 1000)
+            .await
+            .unwrap();
+        assert_eq!(res, (2, 5));
+        assert_eq!(
+            snapshot_rows("s_1000").await,
+            vec![("1".to_owned(), 200, 2), ("2".to_owned(), 200, 2)]
+        );
+
+        // a later snapshot merges the previous one with rows pushed since, even of new
+        // features, and late ones whose event time isn't after the previous snapshot
+        features.push(feature("likes", ValueType::Int64));
+        push("3", 5, 1500, features.clone()).await;
+        push("2", 9, 300, features.clone()).await;
+        push("2", 8, 100, features.clone()).await;
+        let base = Some(SnapshotBase {
+            data_table: "s_1000",
+            unix_milli: 1000,
+            watermark: 5,
+        });
+        let res = super::snapshot(&pool, "offline_stream_1", base, "s_2000", &features, 2000)
+            .await
+            .unwrap();
+        assert_eq!(res, (3, 8));
+        assert_eq!(
+            snapshot_rows("s_2000").await,
+            vec![
+                ("1".to_owned(), 1100, 3),
+                ("2".to_owned(), 300, 9),
+                ("3".to_owned(), 1500, 5),
+            ]
+        );
+
+        let res = super::snapshot(&pool, "offline_stream_1", base, "s_2000", &features, 2000).await;
+        assert_eq!(res, Err(Error::ColumnAlreadyExist("s_2000".to_owned())));
+    }
+
+    #[sqlx::test]
+    async fn join_stream(pool: SqlitePool) {
+        let db = DB { pool };
+        let clicks = feature("clicks", ValueType::Int64);
+        let features = vec![clicks.clone()];
+        let push = |key: &str, value: i64, unix_milli: i64| {
+            let row = Row {
+                entity_key: key.to_owned(),
+                values: vec![Some(FeatureValue::Int64(value))],
+            };
+            db.push("offline_stream_1", &features, row, unix_milli)
+        };
+        let entity_rows = || {
+            [
+                ("1", 50),
+                ("1", 100),
+                ("1", 150),
+                ("2", 150),
+                ("1", 250),
+                ("2", 250),
+            ]
+            .into_iter()
+            .map(|(key, unix_milli)| {
+                Ok::<_, String>(EntityRow {
+                    entity_key: key.to_owned(),
+                    unix_milli,
+                    values: vec![],
+                })
+            })
+        };
+        let join = |sources| {
+            let db = &db;
+            let features = &features;
+            async move {
+                db.join(features, &sources, 0, entity_rows())
+                    .await
+                    .unwrap()
+                    .map_ok(|row| match row[2] {
+                        Some(FeatureValue::Int64(v)) => Some(v),
+                        _ => None,
+                    })
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap()
+            }
+        };
+        let source = |revisions| {
+            BTreeMap::from([(
+                1,
+                JoinSource {
+                    revisions,
+                    stream_table: Some("offline_stream_1".to_owned()),
+                },
+            )])
+        };
+
+        // nothing pushed yet
+        assert_eq!(join(source(vec![])).await, vec![None; 6]);
+        db.create_stream_table("offline_stream_1", &features)
+            .await
+            .unwrap();
+        assert_eq!(join(source(vec![])).await, vec![None; 6]);
+
+        push("1", 1, 100).await.unwrap();
+        push("2", 1, 120).await.unwrap();
+        push("1", 2, 200).await.unwrap();
+        push("1", 3, 200).await.unwrap();
+        push("2", 2, 300).await.unwrap();
+
+        let expected = vec![None, Some(1), Some(1), Some(1), Some(3), Some(1)];
+        // replay the whole log
+        assert_eq!(join(source(vec![])).await, expected);
+
+        // start from a snapshot, which drops the value of key 2 to show it's used
+        let (_, watermark) =
+            super::snapshot(&db.pool, "offline_stream_1", None, "s_200", &features, 200)
+                .await
+                .unwrap();
+        sqlx::query("DELETE FROM s_200 WHERE entity_key = '2'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let snapshots = vec![RevisionRange {
+            data_table: "s_200".to_owned(),
+            start: 200,
+            end: i64::MAX,
+            watermark,
+        }];
+        let mut expected = expected;
+        expected[5] = None;
+        assert_eq!(join(source(snapshots.clone())).await, expected);
+
+        // late events pushed after the snapshot was taken are replayed onto it, and win
+        // over a snapshot row of the same event time
+        push("2", 7, 180).await.unwrap();
+        push("1", 9, 200).await.unwrap();
+        expected[4] = Some(9);
+        expected[5] = Some(7);
+        assert_eq!(join(source(snapshots)).await, expected);
+    }
+
+    #[sqlx::test]
+    async fn join_stream_ranges(pool: SqlitePool) {
+        let db = DB { pool };
+        let features = vec![feature("clicks", ValueType::Int64)];
+        let push = |key: &str, value: i64, unix_milli: i64| {
+            let row = Row {
+                entity_key: key.to_owned(),
+                values: vec![Some(FeatureValue::Int64(value))],
+            };
+            db.push("offline_stream_1", &features, row, unix_milli)
+        };
+
+        db.create_stream_table("offline_stream_1", &features)
+            .await
+            .unwrap();
+        push("1", 1, 100).await.unwrap();
+        push("2", 1, 500).await.unwrap();
+        push("1", 2, 1100).await.unwrap();
+        push("2", 2, 1500).await.unwrap();
+        push("1", 3, 2100).await.unwrap();
+
+        let (_, w_1000) = super::snapshot(
+            &db.pool,
+            "offline_stream_1",
+            None,
+            "s_1000",
+            &features,
+            1000,
+        )
+        .await
+        .unwrap();
+        let base = Some(SnapshotBase {
+            data_table: "s_1000",
+            unix_milli: 1000,
+            watermark: w_1000,
+        });
+        let (_, w_2000) = super::snapshot(
+            &db.pool,
+            "offline_stream_1",
+            base,
+            "s_2000",
+            &features,
+            2000,
+        )
+        .await
+        .unwrap();
+        // a late event pushed after both snapshots
+        push("2", 9, 900).await.unwrap();
+
+        let revisions = vec![
+            RevisionRange {
+                data_table: "s_1000".to_owned(),
+                start: 1000,
+                end: 2000,
+                watermark: w_1000,
+            },
+            RevisionRange {
+                data_table: "s_2000".to_owned(),
+                start: 2000,
+                end: i64::MAX,
+                watermark: w_2000,
+            },
+        ];
+
+        // each range reads only the log rows up to its end, and after a snapshot only
+        // the rows after its time or pushed after it was taken
+        let replayed = |snapshot: Option<&RevisionRange>, end: i64| {
+            let query = replay_rows("offline_stream_1", "", snapshot.is_some());
+            let query = format!("SELECT entity_key, unix_milli FROM ({query}) ORDER BY seq");
+            let pool = db.pool.clone();
+            let binds = snapshot.map(|r| (r.watermark, r.start));
+            async move {
+                let mut query = sqlx::query(&query);
+                if let Some((watermark, start)) = binds {
+                    query = query.bind(watermark).bind(start);
+                }
+                query
+                    .bind(end)
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|r| (r.get(0), r.get(1)))
+                    .collect::<Vec<(String, i64)>>()
+            }
+        };
+        let rows = |rows: &[(&str, i64)]| {
+            rows.iter()
+                .map(|&(key, unix_milli)| (key.to_owned(), unix_milli))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            replayed(None, 1000).await,
+            rows(&[("1", 100), ("2", 500), ("2", 900)])
+        );
+        assert_eq!(
+            replayed(Some(&revisions[0]), 2000).await,
+            rows(&[("1", 1100), ("2", 1500), ("2", 900)])
+        );
+        assert_eq!(
+            replayed(Some(&revisions[1]), i64::MAX).await,
+            rows(&[("1", 2100), ("2", 900)])
+        );
+
+        let entity_rows = [
+            ("1", 500),
+            ("2", 600),
+            ("1", 1200),
+            ("2", 1200),
+            ("2", 1600),
+            ("1", 2500),
+            ("2", 2500),
+        ]
+        .into_iter()
+        .map(|(key, unix_milli)| {
+            Ok::<_, String>(EntityRow {
+                entity_key: key.to_owned(),
+                unix_milli,
+                values: vec![],
+            })
+        });
+        let sources = BTreeMap::from([(
+            1,
+            JoinSource {
+                revisions,
+                stream_table: Some("offline_stream_1".to_owned()),
+            },
+        )]);
+        let values = db
+            .join(&features, &sources, 0, entity_rows)
+            .await
+            .unwrap()
+            .map_ok(|row| match row[2] {
+                Some(FeatureValue::Int64(v)) => Some(v),
+                _ => None,
+            })
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Some(1),
+                Some(1),
+                Some(2),
+                Some(9),
+                Some(2),
+                Some(3),
+                Some(2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use futures::Stream;
+
+use crate::database::metadata::{Feature, FeatureValue};
+use crate::database::online::sqlite;
+use crate::database::Result;
+use crate::feastore::{BackendOpt, Row};
+
+pub(crate) enum OnlineStore {
+    Sqlite(sqlite::DB),
+}
+
+impl OnlineStore {
+    pub(crate) async fn open(opt: BackendOpt) -> Self {
+        if let Some(opt) = opt.sqlite {
+            let db = sqlite::DB::from(opt).await;
+            Self::Sqlite(db)
+        } else {
+            panic!("not backend found here!")
+        }
+    }
+
+    pub(crate) async fn close(&self) {
+        match self {
+            Self::Sqlite(db) => db.close().await,
+        }
+    }
+}
+
+impl OnlineStore {
+    /// Makes sure the online table of the group exists and has a column for every feature.
+    pub(crate) async fn create_table(&self, group_id: i64, features: &[Feature]) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.create_table(group_id, features).await,
+        }
+    }
+
+    /// Gets the values of features in one group, a missing entity key yields all `None`.
+    pub(crate) async fn get(
+        &self,
+        group_id: i64,
+        entity_key: &str,
+        features: &[Feature],
+    ) -> Result<Vec<Option<FeatureValue>>> {
+        match self {
+            Self::Sqlite(db) => db.get(group_id, entity_key, features).await,
+        }
+    }
+
+    /// Gets the values of features in one group for many entity keys at once,
+    /// missing entity keys are left out of the result.
+    pub(crate) async fn multi_get(
+        &self,
+        group_id: i64,
+        entity_keys: &[String],
+        features: &[Feature],
+    ) -> Result<HashMap<String, Vec<Option<FeatureValue>>>> {
+        match self {
+            Self::Sqlite(db) => db.multi_get(group_id, entity_keys, features).await,
+        }
+    }
+
+    /// Writes the values of an entity key in a stream group, unless the stored
+    /// values have a later event time `unix_milli`.
+    pub(crate) async fn push(
+        &self,
+        group_id: i64,
+        features: &[Feature],
+        row: Row,
+        unix_milli: i64,
+    ) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.push(group_id, features, row, unix_milli).await,
+        }
+    }
+
+    /// Replaces all rows of the group's online table with `rows` atomically.
+    /// Returns the number of imported rows.
+    pub(crate) async fn import<S>(
+        &self,
+        group_id: i64,
+        features: &[Feature],
+        rows: S,
+    ) -> Result<u64>
+    where
+        S: Stream<Item = Result<Row>> + Unpin,
+    {
+        match self {
+            Self::Sqlite(db) => db.import(group_id, features, rows).await,
+        }
+    }
+
+    /// Drops the online table of the group, if any.
+    pub(crate) async fn drop_table(&self, group_id: i64) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.drop_table(group_id).await,
+        }
+    }
+
+    /// Drops the column of a feature from the online table of its group, if any.
+    pub(crate) async fn drop_column(&self, group_id: i64, feature_name: &str) -> Result<()> {
+        match self {
+            Self::Sqlite(db) => db.drop_column(group_id, feature_name).await,
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use std::collections::HashMap;
+
+use futures::{Stream, TryStreamExt};
+use itertools::Itertools;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Row as _, SqlitePool};
+
+use crate::database::metadata::{Feature, FeatureValue};
+use crate::database::util::quote;
+use crate::database::{Result, SQLiteOpt};
+use crate::feastore::Row;
+
+/// The default SQLITE_MAX_VARIABLE_NUMBER of SQLite before 3.32.0.
+const MAX_VARIABLE_NUMBER: usize = 999;
+
+pub struct DB {
+    pool: SqlitePool,
+}
+
+impl DB {
+    pub(crate) async fn from(db_file: SQLiteOpt) -> Self {
+        let pool = SqlitePool::connect(format!("sqlite://{}", &db_file.db_file).as_str())
+            .await
+            .unwrap_or_else(|_| panic!("open {} failed!", db_file.db_file));
+
+        Self { pool }
+    }
+
+    pub(crate) async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    pub(crate) async fn create_table(&self, group_id: i64, features: &[Feature]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        create_table(&mut tx, group_id, features).await?;
+        tx.commit().await.map_err(|e| e.into())
+    }
+
+    pub(crate) async fn get(
+        &self,
+        group_id: i64,
+        entity_key: &str,
+        features: &[Feature],
+    ) -> Result<Vec<Option<FeatureValue>>> {
+        get(&self.pool, group_id, entity_key, features).await
+    }
+
+    pub(crate) async fn multi_get(
+        &self,
+        group_id: i64,
+        entity_keys: &[String],
+        features: &[Feature],
+    ) -> Result<HashMap<String, Vec<Option<FeatureValue>>>> {
+        multi_get(&self.pool, group_id, entity_keys, features).await
+    }
+
+    pub(crate) async fn push(
+        &self,
+        group_id: i64,
+        features: &[Feature],
+        row: Row,
+        unix_milli: i64,
+    ) -> Result<()> {
+        push(&self.pool, group_id, features, row, unix_milli).await
+    }
+
+    pub(crate) async fn import<S>(
+        &self,
+        group_id: i64,
+        features: &[Feature],
+        rows: S,
+    ) -> Result<u64>
+    where
+        S: Stream<Item = Result<Row>> + Unpin,
+    {
+        let mut tx = self.pool.begin().await?;
+        let count = import(&mut tx, group_id, features, rows).await?;
+        tx.commit().await?;
+        Ok(count)
+    }
+
+    pub(crate) async fn drop_table(&self, group_id: i64) -> Result<()> {
+        drop_table(&self.pool, group_id).await
+    }
+
+    pub(crate) async fn drop_column(&self, group_id: i64, feature_name: &str) -> Result<()> {
+        drop_column(&self.pool, group_id, feature_name).await
+    }
+}
+
+fn table_name(group_id: i64) -> String {
+    format!("online_{group_id}")
+}
+
+async fn drop_table<'a, A>(conn: A, group_id: i64) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+    let query = format!("DROP TABLE IF EXISTS {}", quote(&table_name(group_id)));
+    sqlx::query(&query).execute(&mut *conn).await?;
+    Ok(())
+}
+
+async fn drop_column<'a, A>(conn: A, group_id: i64, feature_name: &str) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+    let table = quote(&table_name(group_id));
+
+    let exists = sqlx::query(&format!("PRAGMA table_info({table})"))
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .any(|row| {
+            row.try_get::<String, _>("name")
+                .is_ok_and(|n| n == feature_name)
+        });
+    if exists {
+        let query = format!("ALTER TABLE {table} DROP COLUMN {}", quote(feature_name));
+        sqlx::query(&query).execute(&mut *conn).await?;
+    }
+    Ok(())
+}
+
+// functions run in a transaction take the connection rather than `sqlx::Acquire`,
+// as futures holding `&mut *tx` through a generic aren't Send, which serving needs
+async fn create_table(
+    conn: &mut SqliteConnection,
+    group_id: i64,
+    features: &[Feature],
+) -> Result<()> {
+    let table = quote(&table_name(group_id));
+
+    // unix_milli is the event time of the row, only used by stream groups
+    let query = format!(
+        "CREATE TABLE IF NOT EXISTS {table} (entity_key TEXT NOT NULL PRIMARY KEY, unix_milli INTEGER)"
+    );
+    sqlx::query(&query).execute(&mut *conn).await?;
+
+    let columns = sqlx::query(&format!("PRAGMA table_info({table})"))
+        .fetch_all(&mut *conn)
+        .await?
+        .iter()
+        .map(|row| row.try_get::<String, _>("name"))
+        .collect::<std::result::Result<Vec<String>, sqlx::Error>>()?;
+
+    for feature in features {
+        if columns.contains(&feature.name) {
+            continue;
+        }
+
+        let query = format!(
+            "ALTER TABLE {table} ADD COLUMN {} {}",
+            quote(&feature.name),
+            feature.value_type.sqlite_type()
+        );
+        sqlx::query(&query).execute(&mut *conn).await?;
+    }
+
+    Ok(())
+}
+
+/// A missing table means nothing was synced or pushed to the group yet.
+async fn table_exists(conn: &mut SqliteConnection, group_id: i64) -> Result<bool> {
+    let exists = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?)",
+    )
+    .bind(table_name(group_id))
+    .fetch_one(conn)
+    .await?;
+    Ok(exists)
+}
+
+async fn get<'a, A>(
+    conn: A,
+    group_id: i64,
+    entity_key: &str,
+    features: &[Feature],
+) -> Result<Vec<Option<FeatureValue>>>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    if features.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut conn = conn.acquire().await?;
+    if !table_exists(&mut conn, group_id).await? {
+        return Ok(vec![None; features.len()]);
+    }
+
+    let query = format!(
+        "SELECT {} FROM {} WHERE entity_key = ?",
+        features.iter().map(|f| quote(&f.name)).join(", "),
+        quote(&table_name(group_id))
+    );
+    let row = sqlx::query(&query)
+        .bind(entity_key)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(vec![None; features.len()]),
+    };
+
+    features
+        .iter()
+        .enumerate()
+        .map(|(i, f)| FeatureValue::from_sqlite_row(&row, i, &f.value_type))
+        .collect::<std::result::Result<Vec<_>, sqlx::Error>>()
+        .map_err(|e| e.into())
+}
+
+async fn multi_get<'a, A>(
+    conn: A,
+    group_id: i64,
+    entity_keys: &[String],
+    features: &[Feature],
+) -> Result<HashMap<String, Vec<Option<FeatureValue>>>>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut res = HashMap::new();
+    if features.is_empty() || entity_keys.is_empty() {
+        return Ok(res);
+    }
+
+    let mut conn = conn.acquire().await?;
+    if !table_exists(&mut conn, group_id).await? {
+        return Ok(res);
+    }
+    let query_str = format!(
+        "SELECT entity_key, {} FROM {}",
+        features.iter().map(|f| quote(&f.name)).join(", "),
+        quote(&table_name(group_id))
+    );
+
+    for keys in entity_keys.chunks(MAX_VARIABLE_NUMBER) {
+        let query_str = format!(
+            "{query_str} WHERE entity_key in (?{})",
+            ", ?".repeat(keys.len() - 1)
+        );
+        let mut query = sqlx::query(&query_str);
+        for key in keys {
+            query = query.bind(key);
+        }
+
+        for row in query.fetch_all(&mut *conn).await? {
+            let values = features
+                .iter()
+                .enumerate()
+                .map(|(i, f)| FeatureValue::from_sqlite_row(&row, i + 1, &f.value_type))
+                .collect::<std::result::Result<Vec<_>, sqlx::Error>>()?;
+            res.insert(row.try_get("entity_key")?, values);
+        }
+    }
+
+    Ok(res)
+}
+
+/// Upserts the row unless the stored one has a later event time.
+async fn push<'a, A>(
+    conn: A,
+    group_id: i64,
+    features: &[Feature],
+    row: Row,
+    unix_milli: i64,
+) -> Result<()>
+where
+    A: sqlx::Acquire<'a, Database = sqlx::Sqlite>,
+{
+    let mut conn = conn.acquire().await?;
+    let table = quote(&table_name(group_id));
+
+    let query = format!(
+        "INSERT INTO {table} (entity_key, unix_milli{}) VALUES (?, ?{}) ON CONFLICT (entity_key) DO UPDATE SET unix_milli = excluded.unix_milli{} WHERE {table}.unix_milli IS NULL OR {table}.unix_milli <= excluded.unix_milli",
+        features
+            .iter()
+            .map(|f| format!(", {}", quote(&f.name)))
+            .join(""),
+        ", ?".repeat(features.len()),
+        features
+            .iter()
+            .map(|f| format!(", {0} = excluded.{0}", quote(&f.name)))
+            .join("")
+    );
+
+    let mut query = sqlx::query(&query).bind(row.entity_key).bind(unix_milli);
+    for value in row.values {
+        query = query.bind(value);
+    }
+    query.execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+async fn import<S>(
+    conn: &mut SqliteConnection,
+    group_id: i64,
+    features: &[Feature],
+    mut rows: S,
+) -> Result<u64>
+where
+    S: Stream<Item = Result<Row>> + Unpin,
+{
+    let table = quote(&table_name(group_id));
+
+    sqlx::query(&format!("DELETE FROM {table}"))
+        .execute(&mut *conn)
+        .await?;
+
+    let query = format!(
+        "INSERT INTO {table} (entity_key{}) VALUES (?{})",
+        features
+            .iter()
+            .map(|f| format!(", {}", quote(&f.name)))
+            .join(""),
+        ", ?".repeat(features.len())
+    );
+
+    let mut count = 0;
+    while let Some(row) = rows.try_next().await? {
+        if row.values.len() != features.len() {
+            return Err(format!(
+                "expected {} values for entity key '{}', got {}",
+                features.len(),
+                row.entity_key,
+                row.values.len()
+            )
+            .into());
+        }
+
+        let mut query = sqlx::query(&query).bind(row.entity_key);
+        for value in row.values {
+            query = query.bind(value);
+        }
+        query.execute(&mut *conn).await?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Utc;
+
+    use crate::database::metadata::ValueType;
+
+    fn feature(name: &str, value_type: ValueType) -> Feature {
+        Feature {
+            id: 0,
+            name: name.to_owned(),
+            group_id: 1,
+            value_type,
+            description: String::new(),
+            create_time: Utc::now(),
+            modify_time: Utc::now(),
+            group: None,
+        }
+    }
+
+    async fn columns(pool: &SqlitePool, group_id: i64) -> Vec<(String, String)> {
+        sqlx::query(&format!("PRAGMA table_info({})", table_name(group_id)))
+            .fetch_all(pool)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| (row.get("name"), row.get("type")))
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn create_table(pool: SqlitePool) {
+        let features = vec![
+            feature("model", ValueType::StringType),
+            feature("price", ValueType::Int64),
+        ];
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            columns(&pool, 1).await,
+            vec![
+                ("entity_key".to_owned(), "TEXT".to_owned()),
+                ("unix_milli".to_owned(), "INTEGER".to_owned()),
+                ("model".to_owned(), "TEXT".to_owned()),
+                ("price".to_owned(), "INTEGER".to_owned()),
+            ]
+        );
+
+        // creating it again is a no-op
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+        assert_eq!(columns(&pool, 1).await.len(), 4);
+    }
+
+    #[sqlx::test]
+    async fn create_table_with_new_features(pool: SqlitePool) {
+        let mut features = vec![feature("model", ValueType::StringType)];
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        features.push(feature("is_new", ValueType::Bool));
+        features.push(feature("launch_time", ValueType::Time));
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            columns(&pool, 1).await,
+            vec![
+                ("entity_key".to_owned(), "TEXT".to_owned()),
+                ("unix_milli".to_owned(), "INTEGER".to_owned()),
+                ("model".to_owned(), "TEXT".to_owned()),
+                ("is_new".to_owned(), "BOOLEAN".to_owned()),
+                ("launch_time".to_owned(), "DATETIME".to_owned()),
+            ]
+        );
+    }
+
+    #[sqlx::test]
+    async fn drop_table_and_column(pool: SqlitePool) {
+        let features = vec![
+            feature("model", ValueType::StringType),
+            feature("price", ValueType::Int64),
+        ];
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        super::drop_column(&pool, 1, "price").await.unwrap();
+        // dropping a missing column is a no-op
+        super::drop_column(&pool, 1, "price").await.unwrap();
+        assert_eq!(
+            columns(&pool, 1).await,
+            vec![
+                ("entity_key".to_owned(), "TEXT".to_owned()),
+                ("unix_milli".to_owned(), "INTEGER".to_owned()),
+                ("model".to_owned(), "TEXT".to_owned()),
+            ]
+        );
+
+        super::drop_table(&pool, 1).await.unwrap();
+        assert!(columns(&pool, 1).await.is_empty());
+        super::drop_table(&pool, 1).await.unwrap();
+        super::drop_column(&pool, 1, "model").await.unwrap();
+    }
+
+    #[sqlx::test]
+    async fn get(pool: SqlitePool) {
+        let features = vec![
+            feature("model", ValueType::StringType),
+            feature("price", ValueType::Int64),
+            feature("is_new", ValueType::Bool),
+        ];
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        sqlx::query("INSERT INTO online_1 (entity_key, model, price) VALUES ('1', 'xiaomi', 1999)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let values = super::get(&pool, 1, "1", &features).await.unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Some(FeatureValue::String("xiaomi".to_owned())),
+                Some(FeatureValue::Int64(1999)),
+                None,
+            ]
+        );
+
+        let values = super::get(&pool, 1, "1", &features[1..2]).await.unwrap();
+        assert_eq!(values, vec![Some(FeatureValue::Int64(1999))]);
+
+        let values = super::get(&pool, 1, "not_exist", &features).await.unwrap();
+        assert_eq!(values, vec![None, None, None]);
+
+        // nothing was synced or pushed to a group without a table
+        let values = super::get(&pool, 2, "1", &features).await.unwrap();
+        assert_eq!(values, vec![None, None, None]);
+    }
+
+    #[sqlx::test]
+    async fn multi_get(pool: SqlitePool) {
+        let features = vec![feature("price", ValueType::Int64)];
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        let entity_keys: Vec<String> = (0..MAX_VARIABLE_NUMBER * 2 + 1)
+            .map(|i| i.to_string())
+            .collect();
+        for key in entity_keys.iter().step_by(2) {
+            sqlx::query("INSERT INTO online_1 (entity_key, price) VALUES (?, ?)")
+                .bind(key)
+                .bind(key.parse::<i64>().unwrap())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let values = super::multi_get(&pool, 1, &entity_keys, &features)
+            .await
+            .unwrap();
+        assert_eq!(values.len(), MAX_VARIABLE_NUMBER + 1);
+        assert_eq!(values["0"], vec![Some(FeatureValue::Int64(0))]);
+        assert_eq!(values["1998"], vec![Some(FeatureValue::Int64(1998))]);
+        assert!(!values.contains_key("1"));
+
+        let values = super::multi_get(&pool, 1, &[], &features).await.unwrap();
+        assert!(values.is_empty());
+
+        let values = super::multi_get(&pool, 2, &entity_keys, &features)
+            .await
+            .unwrap();
+        assert!(values.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn import(pool: SqlitePool) {
+        let features = vec![
+            feature("model", ValueType::StringType),
+            feature("price", ValueType::Int64),
+        ];
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        let row = |key: &str, price: i64| -> Result<Row> {
+            Ok(Row {
+                entity_key: key.to_owned(),
+                values: vec![None, Some(FeatureValue::Int64(price))],
+            })
+        };
+
+        let rows = futures::stream::iter(vec![row("1", 1), row("2", 2)]);
+        let count = super::import(&mut pool.acquire().await.unwrap(), 1, &features, rows)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        // importing again replaces all rows
+        let rows = futures::stream::iter(vec![row("2", 20), row("3", 30)]);
+        let count = super::import(
+            &mut pool.acquire().await.unwrap(),
+            1,
+            &features[1..],
+            rows.map_ok(|mut r| {
+                r.values.remove(0);
+                r
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(count, 2);
+
+        let keys: Vec<String> = ["1", "2", "3"].iter().map(|k| k.to_string()).collect();
+        let values = super::multi_get(&pool, 1, &keys, &features).await.unwrap();
+        assert_eq!(values.len(), 2);
+        assert_eq!(values["2"], vec![None, Some(FeatureValue::Int64(20))]);
+        assert_eq!(values["3"], vec![None, Some(FeatureValue::Int64(30))]);
+    }
+
+    #[sqlx::test]
+    async fn push(pool: SqlitePool) {
+        let features = vec![
+            feature("clicks", ValueType::Int64),
+            feature("last_post", ValueType::StringType),
+        ];
+        super::create_table(&mut pool.acquire().await.unwrap(), 1, &features)
+            .await
+            .unwrap();
+
+        let push = |clicks: i64, unix_milli: i64| {
+            let row = Row {
+                entity_key: "1".to_owned(),
+                values: vec![Some(FeatureValue::Int64(clicks)), None],
+            };
+            super::push(&pool, 1, &features, row, unix_milli)
+        };
+        let get = || super::get(&pool, 1, "1", &features[..1]);
+
+        push(1, 100).await.unwrap();
+        assert_eq!(get().await.unwrap(), vec![Some(FeatureValue::Int64(1))]);
+
+        push(2, 200).await.unwrap();
+        assert_eq!(get().await.unwrap(), vec![Some(FeatureValue::Int64(2))]);
+
+        // a late event doesn't override a later one
+        push(3, 150).await.unwrap();
+        assert_eq!(get().await.unwrap(), vec![Some(FeatureValue::Int64(2))]);
+
+        // the last write wins on the same event time
+        push(4, 200).await.unwrap();
+        assert_eq!(get().await.unwrap(), vec![Some(FeatureValue::Int64(4))]);
+    }
+}
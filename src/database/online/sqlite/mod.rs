@@ -0,0 +1,3 @@
+mod db;
+
+pub use db::DB;
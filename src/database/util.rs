@@ -0,0 +1,4 @@
+/// Quotes an identifier, e.g. a feature name used as a column name, for SQLite.
+pub(crate) fn quote(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
@@ -1,11 +1,120 @@
-use serde::Deserialize;
+use clap::ValueEnum;
+use serde::Serialize;
 use serde_yaml as yaml;
 
 use std::collections::HashMap;
-use std::io;
+use std::path::Path;
+use std::{fmt, io};
 
 use crate::database::metadata::{RichEntity, RichFeature, RichGroup};
-use crate::feastore::error::Result;
+use crate::feastore::error::{Error, Result};
+use crate::feastore::plan::object;
+use crate::feastore::validate::{
+    child, deserialize, index, line_column, validate, Positions, Problem, SyntaxError,
+};
+use crate::feastore::ValidationError;
+
+/// A manifest file, applied as a whole along with the others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    /// the path of the file, telling where its errors are
+    pub path: String,
+    pub content: String,
+    pub format: Format,
+}
+
+/// The format of a manifest, objects are declared the same way in all of them. A json
+/// or toml manifest holds a single document.
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+pub enum Format {
+    #[default]
+    Yaml,
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// Tells the format of a manifest file by its extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// Parses the documents of a manifest along with their positions, the documents
+    /// before a syntax error if any.
+    fn parse(self, text: &str) -> (Vec<yaml::Value>, Positions, Option<SyntaxError>) {
+        let (value, positions) = match self {
+            Self::Yaml => return Positions::parse(text),
+            // json is yaml as well, but its own errors tell more
+            Self::Json => (
+                serde_json::from_str::<serde_json::Value>(text)
+                    .map_err(|err| SyntaxError {
+                        line: err.line(),
+                        // zero at the end of the text
+                        column: err.column().max(1),
+                        message: err.to_string(),
+                    })
+                    .and_then(to_yaml),
+                Positions::parse(text).1,
+            ),
+            Self::Toml => (
+                toml::from_str::<toml::Value>(text)
+                    .map_err(|err| {
+                        let (line, column) = err
+                            .span()
+                            .map_or((1, 1), |span| line_column(text, span.start));
+                        SyntaxError {
+                            line,
+                            column,
+                            message: err.message().trim().replace('\n', ", "),
+                        }
+                    })
+                    .and_then(to_yaml),
+                Positions::parse_toml(text),
+            ),
+        };
+
+        match value {
+            Ok(value) => (vec![value], positions, None),
+            Err(err) => (Vec::new(), positions, Some(err)),
+        }
+    }
+}
+
+fn to_yaml(value: impl Serialize) -> std::result::Result<yaml::Value, SyntaxError> {
+    yaml::to_value(value).map_err(|err| SyntaxError {
+        line: 1,
+        column: 1,
+        message: err.to_string(),
+    })
+}
+
+/// Where an object is declared in the manifests.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Declaration {
+    /// the manifest file, none for a manifest read from elsewhere
+    pub file: Option<String>,
+    /// the number of the document, counted from 1
+    pub document: usize,
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "document {}", self.document)?;
+        if let Some(file) = &self.file {
+            write!(f, " of {file}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The objects declared in a document with their paths, e.g. `group 'device'` at
+/// `items[0].groups[1]`.
+type Objects = Vec<(String, String)>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct ApplyStage {
@@ -23,92 +132,199 @@ impl ApplyStage {
         }
     }
 
+    #[cfg(test)]
     pub fn from_reader<R: io::Read>(r: R) -> Result<Self> {
-        let mut stage = ApplyStage::new();
+        Self::from_reader_with_documents(r).map(|(stage, _)| stage)
+    }
 
-        for de in yaml::Deserializer::from_reader(r) {
-            let v = yaml::Value::deserialize(de).expect("Unable to parse.");
-            let sub_stage = Self::from_value(v)?;
-            stage.merge(sub_stage);
-        }
+    /// Parses a manifest like `from_reader`, also telling where each object is declared,
+    /// by its name like `group 'device'`.
+    ///
+    /// Every document is validated before, failing with all the problems found.
+    pub(crate) fn from_reader_with_documents<R: io::Read>(
+        mut r: R,
+    ) -> Result<(Self, HashMap<String, Declaration>)> {
+        let mut text = String::new();
+        r.read_to_string(&mut text)
+            .map_err(|e| format!("read manifest failed: {e}"))?;
+        Self::from_sources([(None, text.as_str(), Format::Yaml)])
+    }
 
-        Ok(stage)
+    /// Parses manifest files into one stage, like `from_reader_with_documents`. An object
+    /// is declared once across all of them, the groups of a file may refer to an entity
+    /// declared in another one.
+    pub(crate) fn from_manifests(
+        manifests: &[Manifest],
+    ) -> Result<(Self, HashMap<String, Declaration>)> {
+        Self::from_sources(
+            manifests
+                .iter()
+                .map(|m| (Some(m.path.as_str()), m.content.as_str(), m.format)),
+        )
     }
 
-    fn from_value(value: yaml::Value) -> Result<Self> {
-        let mut stage = Self::new();
-        match parse_kind(&value) {
-            Some("Entity") => {
-                let entity: RichEntity = yaml::from_value(value).expect("Unable to parse");
-                stage.from_entity(entity);
-                Ok(stage)
-            }
-            Some("Group") => {
-                let group: RichGroup = yaml::from_value(value).expect("Unable to parse");
-                stage.from_group(group);
-                Ok(stage)
+    fn from_sources<'a>(
+        sources: impl IntoIterator<Item = (Option<&'a str>, &'a str, Format)>,
+    ) -> Result<(Self, HashMap<String, Declaration>)> {
+        let mut stage = ApplyStage::new();
+        let mut declarations: HashMap<String, Declaration> = HashMap::new();
+        let mut errors = Vec::new();
+
+        for (file, text, format) in sources {
+            let (values, positions, syntax_error) = format.parse(text);
+            let documents = values.len();
+
+            for (i, value) in values.into_iter().enumerate() {
+                let (sub_stage, objects) = match Self::from_value(value) {
+                    Ok(parsed) => parsed,
+                    Err(problems) => {
+                        let located = problems.into_iter().map(|p| positions.locate(file, i, p));
+                        errors.extend(located);
+                        continue;
+                    }
+                };
+
+                for (object, path) in objects {
+                    match declarations.get(&object) {
+                        Some(first) => {
+                            let problem = Problem::new(
+                                &path,
+                                format!("{object} is already declared in {first}"),
+                            );
+                            errors.push(positions.locate(file, i, problem));
+                        }
+                        None => {
+                            let declaration = Declaration {
+                                file: file.map(str::to_string),
+                                document: i + 1,
+                            };
+                            declarations.insert(object, declaration);
+                        }
+                    }
+                }
+                stage.merge(sub_stage);
             }
-            Some("Feature") => {
-                let feature: RichFeature = yaml::from_value(value).expect("Unable to parse");
-                stage.from_feature(feature);
-                Ok(stage)
+
+            if let Some(err) = syntax_error {
+                errors.push(ValidationError {
+                    file: file.map(str::to_string),
+                    document: documents + 1,
+                    line: err.line,
+                    column: err.column,
+                    path: String::new(),
+                    message: err.message,
+                });
             }
-            Some("Items") => Self::from_item_value(value, "Items"),
-            Some("items") => Self::from_item_value(value, "items"),
-            Some(kind) => Err(format!("invalid kind '{}'", kind).into()),
-            None => Err("invalid yaml: missing kind or items".into()),
         }
+
+        if !errors.is_empty() {
+            return Err(Error::Validation(errors));
+        }
+        Ok((stage, declarations))
     }
 
-    fn from_item_value(value: yaml::Value, items: &'static str) -> Result<Self> {
-        let mut stage = Self::new();
-        match parse_items_kind(&value) {
-            Some("Entity") => {
-                let mut entities: HashMap<String, Vec<RichEntity>> =
-                    yaml::from_value(value).expect("Unable to parse");
+    /// Parses a document, also telling the objects declared in it. Every object is
+    /// checked, telling its first problem.
+    fn from_value(value: yaml::Value) -> std::result::Result<(Self, Objects), Vec<Problem>> {
+        let mut problems = validate(&value);
 
-                for e in entities.remove(items).unwrap_or_default() {
-                    stage.from_entity(e);
-                }
+        let objects: Vec<(String, yaml::Value)> = match parse_kind(&value) {
+            _ if value.get("kind").is_some() => vec![(String::new(), value)],
+            Some(items) => {
+                let values = value[items].as_sequence().cloned().unwrap_or_default();
+                values
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, value)| value.is_mapping())
+                    .map(|(i, value)| (index(items, i), value))
+                    .collect()
             }
-            Some("Group") => {
-                let mut groups: HashMap<String, Vec<RichGroup>> =
-                    yaml::from_value(value).expect("Unable to parse");
+            None => Vec::new(),
+        };
 
-                for g in groups.remove(items).unwrap_or_default() {
-                    stage.from_group(g);
-                }
+        let mut stage = Self::new();
+        let mut declared = Vec::new();
+        for (path, value) in objects {
+            if let Err(problem) = stage.add_object(value, &path, &mut declared) {
+                problems.push(problem);
             }
-            Some("Feature") | Some("Features") => {
-                let mut features: HashMap<String, Vec<RichFeature>> =
-                    yaml::from_value(value).expect("Unable to parse");
+        }
+
+        match problems.is_empty() {
+            true => Ok((stage, declared)),
+            false => Err(problems),
+        }
+    }
 
-                for f in features.remove(items).unwrap_or_default() {
-                    stage.from_feature(f);
+    /// Adds an object declared by its kind, listing the objects declared in it.
+    fn add_object(
+        &mut self,
+        mut value: yaml::Value,
+        path: &str,
+        objects: &mut Objects,
+    ) -> std::result::Result<(), Problem> {
+        let kind = match value.get("kind") {
+            None => return Err(Problem::new(&child(path, "kind"), "missing required field")),
+            Some(yaml::Value::String(kind)) => kind.clone(),
+            Some(_) => return Err(Problem::new(&child(path, "kind"), "expected a string")),
+        };
+        let mapping = value.as_mapping_mut();
+        match kind.as_str() {
+            "Entity" => {
+                // the kind of an entity is its tag
+                mapping.map(|m| m.remove("kind"));
+                let entity: RichEntity = deserialize(path, value)?;
+                objects.push((object("entity", &entity.name), path.to_string()));
+                for (i, group) in entity.groups.iter().flatten().enumerate() {
+                    declare_group(group, &index(&child(path, "groups"), i), objects);
                 }
+                self.add_entity(entity);
+            }
+            "Group" => {
+                let group: RichGroup = deserialize(path, value)?;
+                if group.entity_name.is_none() {
+                    return Err(Problem::new(path, "missing field `entity`"));
+                }
+                declare_group(&group, path, objects);
+                self.add_group(group);
+            }
+            // `Features` is accepted in items as well
+            "Feature" | "Features" if kind == "Feature" || !path.is_empty() => {
+                // the category of a feature printed by `get feature` is its group's
+                mapping.map(|m| m.remove("category"));
+                let feature: RichFeature = deserialize(path, value)?;
+                let Some(group) = feature.group_name.as_deref() else {
+                    return Err(Problem::new(path, "missing field `group`"));
+                };
+                let name = format!("{group}.{}", feature.name);
+                objects.push((object("feature", &name), path.to_string()));
+                self.add_feature(feature);
+            }
+            kind => {
+                return Err(Problem::new(
+                    &child(path, "kind"),
+                    format!("invalid kind '{kind}', expected one of: Entity, Group, Feature"),
+                ))
             }
-            Some(kind) => return Err(format!("invalid kind '{}'", kind).into()),
-            None => return Err("invalid yaml: missing kind or items".into()),
         }
-
-        Ok(stage)
+        Ok(())
     }
 
-    fn from_entity(&mut self, mut entity: RichEntity) {
+    fn add_entity(&mut self, mut entity: RichEntity) {
         for group in entity.take_groups().unwrap_or_default() {
-            self.from_group(group);
+            self.add_group(group);
         }
 
         self.new_entities.push(entity);
     }
 
-    fn from_group(&mut self, mut group: RichGroup) {
+    fn add_group(&mut self, mut group: RichGroup) {
         self.new_features
             .extend(group.take_features().unwrap_or_default());
         self.new_groups.push(group);
     }
 
-    fn from_feature(&mut self, feature: RichFeature) {
+    fn add_feature(&mut self, feature: RichFeature) {
         self.new_features.push(feature);
     }
 
@@ -119,6 +335,14 @@ impl ApplyStage {
     }
 }
 
+fn declare_group(group: &RichGroup, path: &str, objects: &mut Objects) {
+    objects.push((object("group", &group.name), path.to_string()));
+    for (i, feature) in group.features.iter().flatten().enumerate() {
+        let name = format!("{}.{}", group.name, feature.name);
+        objects.push((object("feature", &name), index(&child(path, "features"), i)));
+    }
+}
+
 fn parse_kind(value: &yaml::Value) -> Option<&str> {
     if value["kind"].is_string() {
         return value["kind"].as_str();
@@ -132,15 +356,6 @@ fn parse_kind(value: &yaml::Value) -> Option<&str> {
     None
 }
 
-fn parse_items_kind(value: &yaml::Value) -> Option<&str> {
-    if let Some(sequence) = value["items"].as_sequence() {
-        if !sequence.is_empty() {
-            return sequence[0]["kind"].as_str();
-        }
-    }
-    None
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,7 +380,11 @@ name: user
 description: 'User ID'
              "#
                 .as_bytes(),
-                want: Err(s("invalid yaml: missing kind or items").into()),
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 3, 1),
+                    "",
+                    "missing kind or items",
+                )])),
             },
             TestCase {
                 description: "invalid kind",
@@ -175,7 +394,11 @@ name: user
 description: 'description'
              "#
                 .as_bytes(),
-                want: Err(s("invalid kind 'Entit'").into()),
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 2, 1),
+                    "kind",
+                    "invalid kind 'Entit', expected one of: Entity, Group, Feature",
+                )])),
             },
             TestCase {
                 description: "single entity",
@@ -772,6 +995,249 @@ items:
         }
     }
 
+    #[test]
+    fn test_build_apply_stage_json() {
+        struct TestCase {
+            description: &'static str,
+            r: &'static str,
+
+            want: Result<ApplyStage>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                description: "invalid json: missing kind or items",
+                r: r#"
+{
+  "name": "user",
+  "description": "User ID"
+}"#,
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 3, 3),
+                    "",
+                    "missing kind or items",
+                )])),
+            },
+            TestCase {
+                description: "invalid kind",
+                r: r#"
+{
+  "kind": "Entit",
+  "name": "user",
+  "description": "description"
+}"#,
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 3, 3),
+                    "kind",
+                    "invalid kind 'Entit', expected one of: Entity, Group, Feature",
+                )])),
+            },
+            TestCase {
+                description: "single entity",
+                r: r#"{"kind": "Entity", "name": "user", "description": "description"}"#,
+                want: Ok(single_entity()),
+            },
+            TestCase {
+                description: "complex group",
+                r: r#"
+{
+  "kind": "Group",
+  "name": "device",
+  "entity": "user",
+  "category": "batch",
+  "description": "description",
+  "features": [
+    {"name": "model", "value-type": "string", "description": "description"},
+    {"name": "price", "value-type": "int64", "description": "description"}
+  ]
+}"#,
+                want: Ok(complex_group()),
+            },
+            TestCase {
+                description: "entity slice",
+                r: r#"
+{
+  "items": [
+    {
+      "kind": "Entity",
+      "name": "user",
+      "description": "description",
+      "groups": [
+        {
+          "name": "user-click",
+          "category": "stream",
+          "snapshot-interval": 7200,
+          "description": "description",
+          "features": [
+            {"name": "last_5_click_posts", "value-type": "string", "description": "description"}
+          ]
+        }
+      ]
+    },
+    {
+      "kind": "Feature",
+      "name": "gender",
+      "group": "user-click",
+      "value-type": "int64",
+      "description": "description"
+    }
+  ]
+}"#,
+                want: Ok(entity_slice()),
+            },
+            TestCase {
+                description: "invalid value",
+                r: r#"
+{
+  "items": [
+    {
+      "kind": "Feature",
+      "name": "gender",
+      "group": "user-click",
+      "value-type": "int",
+      "description": "description"
+    }
+  ]
+}"#,
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 8, 7),
+                    "items[0].value-type",
+                    "unknown variant `int`, expected one of `string`, `int64`, `float64`, `bool`, `time`, `bytes`",
+                )])),
+            },
+        ];
+
+        for case in test_cases {
+            let stage = ApplyStage::from_sources([(None, case.r, Format::Json)]);
+            assert_eq!(
+                stage.map(|(stage, _)| stage),
+                case.want,
+                "{}",
+                case.description
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_apply_stage_toml() {
+        struct TestCase {
+            description: &'static str,
+            r: &'static str,
+
+            want: Result<ApplyStage>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                description: "invalid toml: missing kind or items",
+                r: r#"
+name = "user"
+description = "User ID"
+"#,
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 2, 1),
+                    "",
+                    "missing kind or items",
+                )])),
+            },
+            TestCase {
+                description: "invalid kind",
+                r: r#"
+kind = "Entit"
+name = "user"
+description = "description"
+"#,
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 2, 1),
+                    "kind",
+                    "invalid kind 'Entit', expected one of: Entity, Group, Feature",
+                )])),
+            },
+            TestCase {
+                description: "single entity",
+                r: r#"
+kind = "Entity"
+name = "user"
+description = "description"
+"#,
+                want: Ok(single_entity()),
+            },
+            TestCase {
+                description: "complex group",
+                r: r#"
+kind = "Group"
+name = "device"
+entity = "user"
+category = "batch"
+description = "description"
+
+[[features]]
+name = "model"
+value-type = "string"
+description = "description"
+
+[[features]]
+name = "price"
+value-type = "int64"
+description = "description"
+"#,
+                want: Ok(complex_group()),
+            },
+            TestCase {
+                description: "entity slice",
+                r: r#"
+[[items]]
+kind = "Entity"
+name = "user"
+description = "description"
+
+[[items.groups]]
+name = "user-click"
+category = "stream"
+snapshot-interval = 7200
+description = "description"
+features = [
+  { name = "last_5_click_posts", value-type = "string", description = "description" },
+]
+
+[[items]]
+kind = "Feature"
+name = "gender"
+group = "user-click"
+value-type = "int64"
+description = "description"
+"#,
+                want: Ok(entity_slice()),
+            },
+            TestCase {
+                description: "invalid value",
+                r: r#"
+[[items]]
+kind = "Feature"
+name = "gender"
+group = "user-click"
+value-type = "int"
+description = "description"
+"#,
+                want: Err(Error::Validation(vec![invalid(
+                    (1, 6, 1),
+                    "items[0].value-type",
+                    "unknown variant `int`, expected one of `string`, `int64`, `float64`, `bool`, `time`, `bytes`",
+                )])),
+            },
+        ];
+
+        for case in test_cases {
+            let stage = ApplyStage::from_sources([(None, case.r, Format::Toml)]);
+            assert_eq!(
+                stage.map(|(stage, _)| stage),
+                case.want,
+                "{}",
+                case.description
+            );
+        }
+    }
+
     #[test]
     fn test_parse_kind() {
         let entity = r#"
@@ -812,38 +1278,337 @@ items:
     }
 
     #[test]
-    fn test_parse_items_kind() {
-        let items = r#"
-items:
-    - kind: Feature
-      name: credit_score
-      group: account
-      value-type: int64
-      description: "credit_score description"
-    - kind: Feature
-      name: account_age_days
-      group: account
-      value-type: int64
-      description: "account_age_days description"
+    fn test_documents() {
+        let r = r#"
+kind: Entity
+name: user
+description: 'description'
+---
+kind: Group
+name: device
+entity: user
+category: batch
+description: 'description'
+features:
+- name: model
+  value-type: string
+  description: 'description'
 "#;
+        let (_, documents) = ApplyStage::from_reader_with_documents(r.as_bytes()).unwrap();
+        assert_eq!(
+            documents,
+            HashMap::from([
+                (s("entity 'user'"), declared(None, 1)),
+                (s("group 'device'"), declared(None, 2)),
+                (s("feature 'device.model'"), declared(None, 2)),
+            ])
+        );
+    }
 
-        let value: serde_yaml::Value = serde_yaml::from_str(items).unwrap();
-        assert_eq!(parse_items_kind(&value), Some("Feature"));
+    #[test]
+    fn test_manifests() {
+        let manifests = [
+            manifest(
+                "registry/device.yaml",
+                r#"
+kind: Group
+name: phone
+entity: device
+category: batch
+description: 'description'
+features:
+- name: model
+  value-type: string
+  description: 'description'
+"#,
+            ),
+            manifest(
+                "registry/entities.yaml",
+                r#"
+kind: Entity
+name: device
+description: 'description'
+"#,
+            ),
+        ];
+        let (stage, documents) = ApplyStage::from_manifests(&manifests).unwrap();
+        assert_eq!(stage.new_entities.len(), 1);
+        assert_eq!(stage.new_groups.len(), 1);
+        assert_eq!(stage.new_features.len(), 1);
+        assert_eq!(
+            documents,
+            HashMap::from([
+                (
+                    s("entity 'device'"),
+                    declared(Some("registry/entities.yaml"), 1)
+                ),
+                (
+                    s("group 'phone'"),
+                    declared(Some("registry/device.yaml"), 1)
+                ),
+                (
+                    s("feature 'phone.model'"),
+                    declared(Some("registry/device.yaml"), 1)
+                ),
+            ])
+        );
+    }
 
-        let items = r#"
+    #[test]
+    fn test_manifests_duplicated() {
+        let manifests = [
+            manifest(
+                "a.yaml",
+                r#"
+kind: Entity
+name: device
+description: 'description'
+groups:
+- name: phone
+  category: batch
+  description: 'description'
+  features:
+  - name: model
+    value-type: string
+    description: 'description'
+"#,
+            ),
+            manifest(
+                "b.yaml",
+                r#"
+kind: Entity
+name: user
+description: 'description'
+---
 items:
-    - kind: Group
-      name: account
-      entity: user
-      category: batch
-      description: user account info
-      features:
-        - name: credit_score
-          value-type: int64
-          description: credit_score description
+- kind: Feature
+  name: model
+  group: phone
+  value-type: string
+  description: 'description'
+"#,
+            ),
+        ];
+        let err = ApplyStage::from_manifests(&manifests).unwrap_err();
+        let mut want = invalid(
+            (2, 7, 3),
+            "items[0]",
+            "feature 'phone.model' is already declared in document 1 of a.yaml",
+        );
+        want.file = Some(s("b.yaml"));
+        assert_eq!(err, Error::Validation(vec![want]));
+    }
+
+    #[test]
+    fn test_validate() {
+        let r = r#"
+kind: Entity
+name: user
+descripton: 'description'
+---
+items:
+- kind: Group
+  name: account
+  entity: user
+  category: batch
+  snapshot-interval: 60
+- kind: Entity
+  name: device
+  description: 'description'
+  groups:
+  - name: phone
+    category: online
+    snapshot-interval: daily
+    description: 'description'
+    features:
+    - name: model
+      value-type: string
+      description: 'description'
+    - name: price
+      value-type: int
+      description: 'description'
+- kind: Featur
+  name: price
+---
+kind: Feature
+name: price
+group: phone
+value-type: [int64]
+description: 'description'
 "#;
-        let value: serde_yaml::Value = serde_yaml::from_str(items).unwrap();
-        assert_eq!(parse_items_kind(&value), Some("Group"));
+        let err = ApplyStage::from_reader(r.as_bytes()).unwrap_err();
+        assert_eq!(
+            err,
+            Error::Validation(vec![
+                invalid(
+                    (1, 4, 1),
+                    "descripton",
+                    "unknown field `descripton`, expected one of `name`, `description`, `groups`"
+                ),
+                invalid((2, 7, 3), "items[0]", "missing field `description`"),
+                invalid(
+                    (2, 17, 5),
+                    "items[1].groups[0].category",
+                    "unknown variant `online`, expected `batch` or `stream`"
+                ),
+                invalid(
+                    (2, 27, 3),
+                    "items[2].kind",
+                    "invalid kind 'Featur', expected one of: Entity, Group, Feature"
+                ),
+                invalid(
+                    (3, 33, 1),
+                    "value-type",
+                    "invalid type: sequence, expected a Value::Tagged enum"
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_validate_syntax() {
+        let r = "kind: Entity\nname: user\n  description: 'description'\n";
+        let Err(Error::Validation(errors)) = ApplyStage::from_reader(r.as_bytes()) else {
+            panic!("a syntax error is expected");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].document, errors[0].line), (1, 3));
+
+        let r = "kind: Entity\nname: user\nname: device\ndescription: 'description'\n";
+        let err = ApplyStage::from_reader(r.as_bytes()).unwrap_err();
+        assert_eq!(
+            err,
+            Error::Validation(vec![invalid((1, 3, 1), "", "duplicate key 'name'")])
+        );
+    }
+
+    #[test]
+    fn test_parse_yaml() {
+        let r = r#"
+kind: Group
+name: &name nan
+entity: '1'
+category: stream
+snapshot-interval: 0x10
+description: *name
+"#;
+        let (documents, _, error) = Format::Yaml.parse(r);
+        assert!(error.is_none());
+        let group: RichGroup = yaml::from_value(documents[0].clone()).unwrap();
+        assert_eq!(
+            (group.name.as_str(), group.entity_name.as_deref()),
+            ("nan", Some("1"))
+        );
+        assert_eq!(group.snapshot_interval, Some(16));
+        assert_eq!(group.description, "nan");
+    }
+
+    fn invalid(
+        (document, line, column): (usize, usize, usize),
+        path: &str,
+        message: &str,
+    ) -> ValidationError {
+        ValidationError {
+            file: None,
+            document,
+            line,
+            column,
+            path: s(path),
+            message: s(message),
+        }
+    }
+
+    fn single_entity() -> ApplyStage {
+        ApplyStage {
+            new_entities: vec![RichEntity {
+                name: s("user"),
+                description: s("description"),
+                groups: None,
+            }],
+            new_groups: vec![],
+            new_features: vec![],
+        }
+    }
+
+    fn complex_group() -> ApplyStage {
+        ApplyStage {
+            new_entities: vec![],
+            new_groups: vec![RichGroup {
+                kind: Some(s("Group")),
+                entity_name: Some(s("user")),
+                name: s("device"),
+                category: Batch,
+                snapshot_interval: None,
+                description: s("description"),
+                features: None,
+            }],
+            new_features: vec![
+                RichFeature {
+                    kind: Some(s("Feature")),
+                    name: s("model"),
+                    group_name: Some(s("device")),
+                    value_type: ValueType::StringType,
+                    description: s("description"),
+                },
+                RichFeature {
+                    kind: Some(s("Feature")),
+                    name: s("price"),
+                    group_name: Some(s("device")),
+                    value_type: ValueType::Int64,
+                    description: s("description"),
+                },
+            ],
+        }
+    }
+
+    fn entity_slice() -> ApplyStage {
+        ApplyStage {
+            new_entities: vec![RichEntity {
+                name: s("user"),
+                description: s("description"),
+                groups: None,
+            }],
+            new_groups: vec![RichGroup {
+                kind: Some(s("Group")),
+                name: s("user-click"),
+                category: Stream,
+                entity_name: Some(s("user")),
+                description: s("description"),
+                snapshot_interval: Some(7200),
+                features: None,
+            }],
+            new_features: vec![
+                RichFeature {
+                    kind: Some(s("Feature")),
+                    name: s("last_5_click_posts"),
+                    group_name: Some(s("user-click")),
+                    value_type: ValueType::StringType,
+                    description: s("description"),
+                },
+                RichFeature {
+                    kind: Some(s("Feature")),
+                    name: s("gender"),
+                    group_name: Some(s("user-click")),
+                    value_type: ValueType::Int64,
+                    description: s("description"),
+                },
+            ],
+        }
+    }
+
+    fn manifest(path: &str, content: &str) -> Manifest {
+        Manifest {
+            path: s(path),
+            content: s(content),
+            format: Format::Yaml,
+        }
+    }
+
+    fn declared(file: Option<&str>, document: usize) -> Declaration {
+        Declaration {
+            file: file.map(s),
+            document,
+        }
     }
 
     fn s(v: impl Into<String>) -> String {
@@ -1,3 +1,7 @@
+use std::fmt::Display;
+
+use crate::feastore::{Conflict, ValidationError};
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, thiserror::Error, PartialEq)]
@@ -7,9 +11,43 @@ pub enum Error {
     #[error("{0}")]
     DataNotFound(String),
     #[error("{0}")]
+    InvalidValue(String),
+    #[error("{0}")]
     SqlxError(String),
     #[error("{0}")]
     Error(String),
+    #[error("apply conflicts with the registered metadata:{}", fmt_list(.0))]
+    Conflict(Vec<Conflict>),
+    #[error("invalid manifest:{}", fmt_list(.0))]
+    Validation(Vec<ValidationError>),
+    #[error(transparent)]
+    Apply(Box<ApplyError>),
+}
+
+/// A failure of applying a manifest, which is rolled back as a whole.
+#[derive(Debug, thiserror::Error, PartialEq)]
+#[error("apply {object}{} failed: {source}", fmt_document(.document, .file))]
+pub struct ApplyError {
+    /// the object failed to apply, e.g. `group 'device'`
+    pub object: String,
+    /// the number of the manifest document declaring the object, counted from 1,
+    /// none for a pruned object
+    pub document: Option<usize>,
+    /// the manifest file declaring the object, none for a manifest read from elsewhere
+    pub file: Option<String>,
+    pub source: Error,
+}
+
+fn fmt_document(document: &Option<usize>, file: &Option<String>) -> String {
+    match (document, file) {
+        (Some(n), Some(file)) => format!(" in document {n} of {file}"),
+        (Some(n), None) => format!(" in document {n}"),
+        (None, _) => String::new(),
+    }
+}
+
+fn fmt_list<T: Display>(items: &[T]) -> String {
+    items.iter().map(|item| format!("\n  {item}")).collect()
 }
 
 impl From<String> for Error {
@@ -32,8 +70,15 @@ impl From<MetadataError> for Error {
             MetadataError::ColumnNotFound(entity, id) => {
                 Self::DataNotFound(format!("{} not found by id {}", entity, id))
             }
+            err @ MetadataError::InvalidValue { .. } => Self::InvalidValue(err.to_string()),
             MetadataError::SqlxError(msg) => Self::SqlxError(msg),
             MetadataError::Other(msg) => Self::Error(msg),
+            MetadataError::Apply { object, source } => Self::Apply(Box::new(ApplyError {
+                object,
+                document: None,
+                file: None,
+                source: (*source).into(),
+            })),
         }
     }
 }
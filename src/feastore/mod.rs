@@ -1,12 +1,39 @@
 pub mod apply;
 mod error;
 mod opt;
+pub(crate) mod plan;
 mod store;
+mod types;
+mod validate;
 
 pub use store::Store;
+pub use types::EntityRow;
+pub use types::ExportResult;
+pub use types::FeatureValues;
+pub use types::ImportResult;
+pub use types::JoinResult;
+pub use types::Row;
+pub use types::SnapshotResult;
+pub use types::SyncResult;
 
 pub use opt::BackendOpt;
+pub use opt::ExportOpt;
 pub use opt::FeatureStoreConfig;
+pub use opt::ImportOpt;
+pub use opt::JoinOpt;
+pub use opt::PushOpt;
 
+pub use apply::Format;
+pub use apply::Manifest;
+
+pub use plan::ApplyPlan;
+pub use plan::Change;
+pub use plan::Conflict;
+pub use plan::Prune;
+pub use plan::Update;
+
+pub use validate::ValidationError;
+
+pub use error::ApplyError;
 pub use error::Error;
 pub use error::Result;
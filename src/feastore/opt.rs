@@ -1,13 +1,51 @@
 use serde::{Deserialize, Serialize};
 
+use crate::database::metadata::FeatureValue;
 use crate::database::SQLiteOpt;
 
 #[derive(Serialize, Deserialize)]
 pub struct FeatureStoreConfig {
     pub metadata: BackendOpt,
+    pub online: Option<BackendOpt>,
+    pub offline: Option<BackendOpt>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct BackendOpt {
     pub sqlite: Option<SQLiteOpt>,
 }
+
+pub struct ImportOpt {
+    pub group_name: String,
+    /// names of the features, in the same order as the values of each row
+    pub features: Vec<String>,
+    /// unix milliseconds, the import time is used if it's not given
+    pub revision: Option<i64>,
+    pub description: String,
+}
+
+pub struct PushOpt {
+    pub entity_key: String,
+    pub group_name: String,
+    /// names of all features in the group, in the same order as `values`
+    pub features: Vec<String>,
+    pub values: Vec<Option<FeatureValue>>,
+    /// the event time in unix milliseconds, the push time is used if it's not given
+    pub unix_milli: Option<i64>,
+}
+
+pub struct JoinOpt {
+    /// full names of the features, e.g. `device.model`
+    pub features: Vec<String>,
+    /// names of the extra values of each entity row
+    pub value_names: Vec<String>,
+}
+
+pub struct ExportOpt {
+    pub group_name: String,
+    /// names of the features in the group, all features if empty
+    pub features: Vec<String>,
+    /// unix milliseconds to export the group as of, a batch group is exported
+    /// at its latest revision not after it; defaults to now, i.e. the latest data
+    pub unix_milli: Option<i64>,
+}
@@ -0,0 +1,671 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::database::metadata::{Entity, Feature, Group, RichEntity, RichFeature, RichGroup};
+use crate::feastore::apply::{ApplyStage, Declaration};
+use crate::feastore::error::{Error, Result};
+
+/// The changes applying a manifest makes to the registered metadata, which is
+/// what `Store::apply_plan` executes.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ApplyPlan {
+    pub create_entities: Vec<RichEntity>,
+    pub create_groups: Vec<RichGroup>,
+    pub create_features: Vec<RichFeature>,
+
+    pub update_entities: Vec<Update>,
+    pub update_groups: Vec<Update>,
+    pub update_features: Vec<Update>,
+
+    /// fields differing from the registered ones which can't be changed, a plan having
+    /// conflicts can't be applied
+    pub conflicts: Vec<Conflict>,
+
+    /// registered objects absent from the manifest, only planned with prune
+    pub prune: Prune,
+
+    /// where each object is declared in the manifests
+    pub(crate) documents: HashMap<String, Declaration>,
+}
+
+/// A change of a registered object.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Update {
+    pub id: i64,
+    /// the full name for a feature, e.g. `device.model`
+    pub name: String,
+    pub change: Change,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Change {
+    Description {
+        from: String,
+        to: String,
+    },
+    /// only for groups, snapshots already taken are kept
+    SnapshotInterval {
+        from: Option<i32>,
+        to: Option<i32>,
+    },
+}
+
+/// A field of a registered object which the manifest declares differently but can't be
+/// changed, e.g. the value type of a feature whose values are stored.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Conflict {
+    /// `entity`, `group` or `feature`
+    pub kind: &'static str,
+    /// the full name for a feature, e.g. `device.model`
+    pub name: String,
+    /// the field name in manifests, e.g. `value-type`
+    pub field: &'static str,
+    pub current: String,
+    pub desired: String,
+}
+
+/// Registered objects absent from a manifest, which are deleted when it's applied with prune.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Prune {
+    pub entities: Vec<String>,
+    pub groups: Vec<String>,
+    /// full names of the features, e.g. `device.model`
+    pub features: Vec<String>,
+}
+
+impl Prune {
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.groups.is_empty() && self.features.is_empty()
+    }
+}
+
+impl ApplyPlan {
+    pub fn is_empty(&self) -> bool {
+        self.create_count() == 0 && self.update_count() == 0 && self.prune.is_empty()
+    }
+
+    fn create_count(&self) -> usize {
+        self.create_entities.len() + self.create_groups.len() + self.create_features.len()
+    }
+
+    fn update_count(&self) -> usize {
+        self.update_entities.len() + self.update_groups.len() + self.update_features.len()
+    }
+
+    fn delete_count(&self) -> usize {
+        self.prune.entities.len() + self.prune.groups.len() + self.prune.features.len()
+    }
+
+    fn conflict(
+        &mut self,
+        kind: &'static str,
+        name: &str,
+        field: &'static str,
+        current: String,
+        desired: String,
+    ) {
+        if current != desired {
+            self.conflicts.push(Conflict {
+                kind,
+                name: name.to_owned(),
+                field,
+                current,
+                desired,
+            });
+        }
+    }
+}
+
+/// Prints the plan like `terraform plan`, one line per change.
+impl fmt::Display for ApplyPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() && self.conflicts.is_empty() {
+            return writeln!(f, "No changes.");
+        }
+
+        for entity in &self.create_entities {
+            writeln!(f, "+ entity '{}'", entity.name)?;
+        }
+        for group in &self.create_groups {
+            writeln!(f, "+ group '{}'", group.name)?;
+        }
+        for feature in &self.create_features {
+            let group = feature.group_name.as_deref().unwrap_or_default();
+            writeln!(f, "+ feature '{group}.{}'", feature.name)?;
+        }
+
+        for (kind, updates) in [
+            ("entity", &self.update_entities),
+            ("group", &self.update_groups),
+            ("feature", &self.update_features),
+        ] {
+            for update in updates {
+                match update.change {
+                    Change::Description { ref from, ref to } => writeln!(
+                        f,
+                        "~ {kind} '{}': description '{from}' -> '{to}'",
+                        update.name
+                    )?,
+                    Change::SnapshotInterval { from, to } => writeln!(
+                        f,
+                        "~ {kind} '{}': snapshot-interval {} -> {}",
+                        update.name,
+                        interval(from),
+                        interval(to)
+                    )?,
+                }
+            }
+        }
+
+        for (kind, names) in [
+            ("entity", &self.prune.entities),
+            ("group", &self.prune.groups),
+            ("feature", &self.prune.features),
+        ] {
+            for name in names {
+                writeln!(f, "- {kind} '{name}'")?;
+            }
+        }
+
+        for conflict in &self.conflicts {
+            writeln!(f, "! {conflict}")?;
+        }
+
+        writeln!(
+            f,
+            "Plan: {} to create, {} to update, {} to delete, {} in conflict.",
+            self.create_count(),
+            self.update_count(),
+            self.delete_count(),
+            self.conflicts.len()
+        )
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} '{}': {} can't be changed from {} to {}",
+            self.kind, self.name, self.field, self.current, self.desired
+        )
+    }
+}
+
+impl ApplyStage {
+    /// Diffs the stage against the registered metadata. With `prune`, the registered
+    /// objects absent from the stage are planned to be deleted. The features must be
+    /// listed with their groups.
+    pub(crate) fn plan(
+        self,
+        entities: &[Entity],
+        groups: &[Group],
+        features: &[Feature],
+        prune: bool,
+    ) -> Result<ApplyPlan> {
+        let mut plan = ApplyPlan::default();
+        if prune {
+            plan.prune = self.prune(entities, groups, features);
+        }
+
+        for entity in self.new_entities {
+            match entities.iter().find(|e| e.name == entity.name) {
+                Some(old) => {
+                    if let Some(update) =
+                        description(old.id, &old.name, &old.description, &entity.description)
+                    {
+                        plan.update_entities.push(update);
+                    }
+                }
+                None => upsert(&mut plan.create_entities, entity, |a, b| a.name == b.name),
+            }
+        }
+
+        for group in self.new_groups {
+            let entity_name = group.entity_name.clone().ok_or_else(|| {
+                Error::InvalidValue(format!("group '{}' has no entity", group.name))
+            })?;
+
+            match groups.iter().find(|g| g.name == group.name) {
+                Some(old) => {
+                    plan.conflict(
+                        "group",
+                        &old.name,
+                        "entity",
+                        old.entity_name.clone(),
+                        entity_name,
+                    );
+                    plan.conflict(
+                        "group",
+                        &old.name,
+                        "category",
+                        old.category.to_string(),
+                        group.category.to_string(),
+                    );
+                    if old.snapshot_interval != group.snapshot_interval {
+                        plan.update_groups.push(Update {
+                            id: old.id,
+                            name: old.name.clone(),
+                            change: Change::SnapshotInterval {
+                                from: old.snapshot_interval,
+                                to: group.snapshot_interval,
+                            },
+                        });
+                    }
+                    if let Some(update) =
+                        description(old.id, &old.name, &old.description, &group.description)
+                    {
+                        plan.update_groups.push(update);
+                    }
+                }
+                None => {
+                    if !entities.iter().any(|e| e.name == entity_name)
+                        && !plan.create_entities.iter().any(|e| e.name == entity_name)
+                    {
+                        return Err(Error::DataNotFound(format!(
+                            "entity '{entity_name}' of group '{}' not found",
+                            group.name
+                        )));
+                    }
+                    upsert(&mut plan.create_groups, group, |a, b| a.name == b.name);
+                }
+            }
+        }
+
+        for feature in self.new_features {
+            let group_name = feature.group_name.clone().ok_or_else(|| {
+                Error::InvalidValue(format!("feature '{}' has no group", feature.name))
+            })?;
+            let full_name = format!("{group_name}.{}", feature.name);
+
+            match features.iter().find(|f| f.full_name() == full_name) {
+                Some(old) => {
+                    plan.conflict(
+                        "feature",
+                        &full_name,
+                        "value-type",
+                        old.value_type.to_string(),
+                        feature.value_type.to_string(),
+                    );
+                    if let Some(update) =
+                        description(old.id, &full_name, &old.description, &feature.description)
+                    {
+                        plan.update_features.push(update);
+                    }
+                }
+                None => {
+                    if !groups.iter().any(|g| g.name == group_name)
+                        && !plan.create_groups.iter().any(|g| g.name == group_name)
+                    {
+                        return Err(Error::DataNotFound(format!(
+                            "group '{group_name}' of feature '{}' not found",
+                            feature.name
+                        )));
+                    }
+                    upsert(&mut plan.create_features, feature, |a, b| {
+                        a.name == b.name && a.group_name == b.group_name
+                    });
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Lists the registered objects which the stage neither declares nor refers to,
+    /// a group referred to by a feature and an entity referred to by a group are kept.
+    /// The features of a pruned group and the groups of a pruned entity are listed too.
+    fn prune(&self, entities: &[Entity], groups: &[Group], features: &[Feature]) -> Prune {
+        let mut kept_groups: HashSet<&str> =
+            self.new_groups.iter().map(|g| g.name.as_str()).collect();
+        kept_groups.extend(
+            self.new_features
+                .iter()
+                .filter_map(|f| f.group_name.as_deref()),
+        );
+
+        let mut kept_entities: HashSet<&str> =
+            self.new_entities.iter().map(|e| e.name.as_str()).collect();
+        kept_entities.extend(
+            self.new_groups
+                .iter()
+                .filter_map(|g| g.entity_name.as_deref()),
+        );
+        kept_entities.extend(
+            groups
+                .iter()
+                .filter(|g| kept_groups.contains(g.name.as_str()))
+                .map(|g| g.entity_name.as_str()),
+        );
+
+        let kept_features: HashSet<String> = self
+            .new_features
+            .iter()
+            .filter_map(|f| Some(format!("{}.{}", f.group_name.as_deref()?, f.name)))
+            .collect();
+
+        Prune {
+            entities: entities
+                .iter()
+                .filter(|e| !kept_entities.contains(e.name.as_str()))
+                .map(|e| e.name.clone())
+                .collect(),
+            groups: groups
+                .iter()
+                .filter(|g| !kept_groups.contains(g.name.as_str()))
+                .map(|g| g.name.clone())
+                .collect(),
+            features: features
+                .iter()
+                .map(Feature::full_name)
+                .filter(|name| !kept_features.contains(name))
+                .collect(),
+        }
+    }
+}
+
+/// Names an object of a plan, e.g. `group 'device'`, with the full name for a feature.
+pub(crate) fn object(kind: &str, name: &str) -> String {
+    format!("{kind} '{name}'")
+}
+
+fn description(id: i64, name: &str, from: &str, to: &str) -> Option<Update> {
+    (from != to).then(|| Update {
+        id,
+        name: name.to_owned(),
+        change: Change::Description {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        },
+    })
+}
+
+fn interval(snapshot_interval: Option<i32>) -> String {
+    snapshot_interval.map_or_else(|| "none".to_owned(), |v| v.to_string())
+}
+
+/// Adds an object to create, a later one of the same name replaces the earlier one.
+fn upsert<T>(items: &mut Vec<T>, item: T, same: impl Fn(&T, &T) -> bool) {
+    match items.iter_mut().find(|i| same(i, &item)) {
+        Some(i) => *i = item,
+        None => items.push(item),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::metadata::{Category, ValueType};
+
+    fn s(v: impl Into<String>) -> String {
+        v.into()
+    }
+
+    fn stage(manifest: &str) -> ApplyStage {
+        ApplyStage::from_reader(manifest.as_bytes()).unwrap()
+    }
+
+    fn entity(id: i64, name: &str) -> Entity {
+        let now = chrono::Utc::now();
+        Entity {
+            id,
+            name: s(name),
+            description: s("description"),
+            create_time: now,
+            modify_time: now,
+        }
+    }
+
+    fn group(id: i64, name: &str, entity_name: &str) -> Group {
+        Group {
+            id,
+            name: s(name),
+            entity_name: s(entity_name),
+            description: s("description"),
+            ..Default::default()
+        }
+    }
+
+    fn feature(id: i64, group: &Group, name: &str) -> Feature {
+        let now = chrono::Utc::now();
+        let mut feature = Feature {
+            id,
+            name: s(name),
+            group_id: group.id,
+            value_type: ValueType::Int64,
+            description: s("description"),
+            create_time: now,
+            modify_time: now,
+            group: Some(Default::default()),
+        };
+        if let Some(ref mut g) = feature.group {
+            g.id = group.id;
+            g.name = group.name.clone();
+        }
+        feature
+    }
+
+    #[test]
+    fn test_plan() {
+        let entities = [entity(1, "user")];
+        let groups = [group(1, "device", "user")];
+        let features = [feature(1, &groups[0], "price")];
+
+        let plan = stage(
+            r#"
+kind: Entity
+name: user
+description: 'user'
+groups:
+- name: device
+  category: stream
+  snapshot-interval: 60
+  description: description
+  features:
+  - name: price
+    value-type: string
+    description: 'description'
+  - name: model
+    value-type: string
+    description: 'description'
+---
+kind: Entity
+name: shop
+description: 'description'
+"#,
+        )
+        .plan(&entities, &groups, &features, false)
+        .unwrap();
+
+        assert_eq!(
+            plan,
+            ApplyPlan {
+                create_entities: vec![RichEntity {
+                    name: s("shop"),
+                    description: s("description"),
+                    groups: None,
+                }],
+                create_features: vec![RichFeature {
+                    kind: Some(s("Feature")),
+                    name: s("model"),
+                    group_name: Some(s("device")),
+                    value_type: ValueType::StringType,
+                    description: s("description"),
+                }],
+                update_entities: vec![Update {
+                    id: 1,
+                    name: s("user"),
+                    change: Change::Description {
+                        from: s("description"),
+                        to: s("user"),
+                    },
+                }],
+                update_groups: vec![Update {
+                    id: 1,
+                    name: s("device"),
+                    change: Change::SnapshotInterval {
+                        from: None,
+                        to: Some(60),
+                    },
+                }],
+                conflicts: vec![
+                    Conflict {
+                        kind: "group",
+                        name: s("device"),
+                        field: "category",
+                        current: s("batch"),
+                        desired: s("stream"),
+                    },
+                    Conflict {
+                        kind: "feature",
+                        name: s("device.price"),
+                        field: "value-type",
+                        current: s("int64"),
+                        desired: s("string"),
+                    },
+                ],
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            plan.to_string(),
+            "\
++ entity 'shop'
++ feature 'device.model'
+~ entity 'user': description 'description' -> 'user'
+~ group 'device': snapshot-interval none -> 60
+! group 'device': category can't be changed from batch to stream
+! feature 'device.price': value-type can't be changed from int64 to string
+Plan: 2 to create, 2 to update, 0 to delete, 2 in conflict.
+"
+        );
+    }
+
+    #[test]
+    fn test_plan_no_changes() {
+        let entities = [entity(1, "user")];
+        let plan = stage(
+            r#"
+kind: Entity
+name: user
+description: 'description'
+"#,
+        )
+        .plan(&entities, &[], &[], false)
+        .unwrap();
+
+        assert_eq!(plan, ApplyPlan::default());
+        assert_eq!(plan.to_string(), "No changes.\n");
+    }
+
+    #[test]
+    fn test_plan_unknown_reference() {
+        let res = stage(
+            r#"
+kind: Group
+name: device
+entity: user
+category: batch
+description: 'description'
+"#,
+        )
+        .plan(&[], &[], &[], false);
+        assert_eq!(
+            res,
+            Err(Error::DataNotFound(s(
+                "entity 'user' of group 'device' not found"
+            )))
+        );
+
+        // a group created in the same plan
+        let plan = stage(
+            r#"
+kind: Group
+name: device
+entity: user
+category: batch
+description: 'description'
+features:
+- name: model
+  value-type: string
+  description: 'description'
+"#,
+        )
+        .plan(&[entity(1, "user")], &[], &[], false)
+        .unwrap();
+        assert_eq!(
+            (
+                plan.create_groups[0].category.clone(),
+                plan.create_features.len()
+            ),
+            (Category::Batch, 1)
+        );
+
+        let res = stage(
+            r#"
+kind: Feature
+name: model
+group: device
+value-type: string
+description: 'description'
+"#,
+        )
+        .plan(&[], &[], &[], false);
+        assert_eq!(
+            res,
+            Err(Error::DataNotFound(s(
+                "group 'device' of feature 'model' not found"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let stage = stage(
+            r#"
+kind: Group
+name: device
+entity: user
+category: batch
+description: 'description'
+features:
+- name: model
+  value-type: string
+  description: 'description'
+---
+kind: Feature
+name: age
+group: student
+value-type: int64
+description: 'description'
+"#,
+        );
+
+        let entities = [entity(1, "user"), entity(2, "person"), entity(3, "shop")];
+        let groups = [
+            group(1, "device", "user"),
+            group(2, "student", "person"),
+            group(3, "account", "user"),
+            group(4, "order", "shop"),
+        ];
+        let features = [
+            feature(1, &groups[0], "model"),
+            feature(2, &groups[0], "price"),
+            feature(3, &groups[1], "age"),
+            feature(4, &groups[1], "gender"),
+            feature(5, &groups[3], "amount"),
+        ];
+
+        let plan = stage.plan(&entities, &groups, &features, true).unwrap();
+        assert_eq!(
+            plan.prune,
+            Prune {
+                entities: vec![s("shop")],
+                groups: vec![s("account"), s("order")],
+                features: vec![s("device.price"), s("student.gender"), s("order.amount")],
+            }
+        );
+        assert!(plan.create_count() == 0 && plan.update_count() == 0);
+    }
+}
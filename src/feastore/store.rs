@@ -1,30 +1,191 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use itertools::Itertools;
+
 use crate::database::metadata::{
-    CreateFeatureOpt, CreateGroupOpt, DataStore, Entity, Feature, GetOpt, Group, ListFeatureOpt,
-    ListOpt, RichEntity, RichFeature, RichGroup,
+    Category, CreateFeatureOpt, CreateGroupOpt, CreateRevisionOpt, DataStore, Entity, Feature,
+    GetOpt, GetRevisionOpt, Group, ListFeatureOpt, ListOpt, ListRevisionOpt, Revision, RichEntity,
+    RichFeature, RichGroup,
+};
+use crate::database::offline::{JoinSource, OfflineStore, RevisionRange, SnapshotBase};
+use crate::database::online::OnlineStore;
+use crate::database::Error as DatabaseError;
+use crate::feastore::{
+    apply, ApplyPlan, EntityRow, ExportOpt, ExportResult, FeatureStoreConfig, FeatureValues,
+    ImportOpt, ImportResult, JoinOpt, JoinResult, Manifest, PushOpt, Result, Row, SnapshotResult,
+    SyncResult,
 };
-use crate::feastore::{apply, FeatureStoreConfig, Result};
 use crate::Error;
 
 pub struct Store {
     metadata: DataStore,
+    online: Option<OnlineStore>,
+    offline: Option<OfflineStore>,
 }
 
 impl Store {
     pub async fn open(opt: FeatureStoreConfig) -> Store {
         let metadata_store = DataStore::open(opt.metadata).await;
+        let online_store = match opt.online {
+            Some(opt) => Some(OnlineStore::open(opt).await),
+            None => None,
+        };
+        let offline_store = match opt.offline {
+            Some(opt) => Some(OfflineStore::open(opt).await),
+            None => None,
+        };
 
         Store {
             metadata: metadata_store,
+            online: online_store,
+            offline: offline_store,
         }
     }
 
     pub async fn apply<R: std::io::Read>(&self, r: R) -> Result<()> {
-        let stage = apply::ApplyStage::from_reader(r)?;
-        self.metadata.apply(stage).await.map_err(|e| e.into())
+        let plan = self.plan(r, false).await?;
+        self.apply_plan(&plan).await
+    }
+
+    /// Diffs a manifest against the registered metadata without changing anything.
+    /// With `prune`, the registered objects absent from the manifest are planned to be deleted.
+    pub async fn plan<R: std::io::Read>(&self, r: R, prune: bool) -> Result<ApplyPlan> {
+        let (stage, documents) = apply::ApplyStage::from_reader_with_documents(r)?;
+        self.plan_stage(stage, documents, prune).await
+    }
+
+    /// Plans manifest files like `plan`, all of them make up one manifest.
+    pub async fn plan_manifests(&self, manifests: &[Manifest], prune: bool) -> Result<ApplyPlan> {
+        let (stage, documents) = apply::ApplyStage::from_manifests(manifests)?;
+        self.plan_stage(stage, documents, prune).await
+    }
+
+    async fn plan_stage(
+        &self,
+        stage: apply::ApplyStage,
+        documents: HashMap<String, apply::Declaration>,
+        prune: bool,
+    ) -> Result<ApplyPlan> {
+        let entities = self.metadata.list_entity(ListOpt::All).await?;
+        let groups = self.metadata.list_group(ListOpt::All).await?;
+        let features = self.list_feature(&[]).await?;
+
+        let mut plan = stage.plan(&entities, &groups, &features, prune)?;
+        plan.documents = documents;
+        Ok(plan)
+    }
+
+    /// Executes a plan, the pruned objects are deleted along with their online and
+    /// offline data. A plan having conflicts fails without changing anything.
+    pub async fn apply_plan(&self, plan: &ApplyPlan) -> Result<()> {
+        if !plan.conflicts.is_empty() {
+            return Err(Error::Conflict(plan.conflicts.clone()));
+        }
+
+        let prune = &plan.prune;
+        let group_ids: Vec<i64> = self
+            .metadata
+            .list_group(ListOpt::All)
+            .await?
+            .into_iter()
+            .filter(|g| prune.groups.contains(&g.name))
+            .map(|g| g.id)
+            .collect();
+        let tables = self.data_tables(&group_ids).await?;
+        // features of the pruned groups go with the tables
+        let mut features = Vec::new();
+        for feature in self.resolve_features(&prune.features).await? {
+            if !group_ids.contains(&feature.group_id) {
+                let tables = self.data_tables(&[feature.group_id]).await?;
+                features.push((feature, tables));
+            }
+        }
+
+        self.metadata
+            .apply(plan)
+            .await
+            .map_err(|err| match Error::from(err) {
+                Error::Apply(mut err) => {
+                    if let Some(declaration) = plan.documents.get(&err.object) {
+                        err.document = Some(declaration.document);
+                        err.file = declaration.file.clone();
+                    }
+                    Error::Apply(err)
+                }
+                err => err,
+            })?;
+
+        for (feature, tables) in features {
+            self.drop_column(&feature, &tables).await?;
+        }
+        self.drop_data(&group_ids, &tables).await?;
+
+        // only the groups created or having features created need their tables changed
+        let group_names: Vec<&str> = plan
+            .create_groups
+            .iter()
+            .map(|g| g.name.as_str())
+            .chain(
+                plan.create_features
+                    .iter()
+                    .filter_map(|f| f.group_name.as_deref()),
+            )
+            .unique()
+            .collect();
+        if !group_names.is_empty() {
+            for group in self
+                .metadata
+                .list_group(ListOpt::Names(group_names))
+                .await?
+            {
+                self.create_tables(&group).await?;
+            }
+        }
+        Ok(())
     }
 
     pub async fn close(&self) {
         self.metadata.close().await;
+        if let Some(ref online) = self.online {
+            online.close().await;
+        }
+        if let Some(ref offline) = self.offline {
+            offline.close().await;
+        }
+    }
+
+    /// Makes sure the tables written by pushes and syncs exist, with a column for each
+    /// feature of the group: its online table, and the offline log of a stream group.
+    async fn create_tables(&self, group: &Group) -> Result<()> {
+        if self.online.is_none() && self.offline.is_none() {
+            return Ok(());
+        }
+
+        let features = self
+            .metadata
+            .list_feature(ListFeatureOpt::GroupIDs(vec![group.id]))
+            .await?;
+        if let Some(ref online) = self.online {
+            online.create_table(group.id, &features).await?;
+        }
+        if let Some(ref offline) = self.offline {
+            if group.category == Category::Stream {
+                offline
+                    .create_stream_table(&stream_table(group.id), &features)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_group_by_id(&self, id: i64) -> Result<Group> {
+        self.metadata
+            .get_group(GetOpt::ID(id))
+            .await?
+            .ok_or_else(|| Error::DataNotFound(format!("group not found by id {id}")))
     }
 
     pub async fn create_entity(&self, name: &str, description: &str) -> Result<i64> {
@@ -46,7 +207,9 @@ impl Store {
     }
 
     pub async fn create_group(&self, opt: CreateGroupOpt) -> Result<i64> {
-        self.metadata.create_group(opt).await.map_err(|e| e.into())
+        let id = self.metadata.create_group(opt).await?;
+        self.create_tables(&self.get_group_by_id(id).await?).await?;
+        Ok(id)
     }
 
     pub async fn get_group<'a>(&self, opt: GetOpt<'a>) -> Result<Option<Group>> {
@@ -61,10 +224,11 @@ impl Store {
     }
 
     pub async fn create_feature(&self, opt: CreateFeatureOpt) -> Result<i64> {
-        self.metadata
-            .create_feature(opt)
-            .await
-            .map_err(|e| e.into())
+        let group_id = opt.group_id;
+        let id = self.metadata.create_feature(opt).await?;
+        self.create_tables(&self.get_group_by_id(group_id).await?)
+            .await?;
+        Ok(id)
     }
 
     pub async fn get_feature<'a>(&self, opt: GetOpt<'a>) -> Result<Option<Feature>> {
@@ -78,6 +242,46 @@ impl Store {
             .map_err(|e| e.into())
     }
 
+    /// Deletes an entity. With `cascade` its groups are deleted along with it, as
+    /// `delete_group` does, otherwise an entity having groups isn't deleted.
+    pub async fn delete_entity(&self, id: i64, cascade: bool) -> Result<()> {
+        let group_ids: Vec<i64> = self
+            .metadata
+            .list_group(ListOpt::All)
+            .await?
+            .into_iter()
+            .filter(|g| g.entity_id == id)
+            .map(|g| g.id)
+            .collect();
+        let tables = self.data_tables(&group_ids).await?;
+
+        self.metadata.delete_entity(id, cascade).await?;
+        self.drop_data(&group_ids, &tables).await
+    }
+
+    /// Deletes a group with its revisions, and drops its online table and offline data.
+    /// With `cascade` its features are deleted along with it, otherwise a group having
+    /// features isn't deleted.
+    pub async fn delete_group(&self, id: i64, cascade: bool) -> Result<()> {
+        let tables = self.data_tables(&[id]).await?;
+
+        self.metadata.delete_group(id, cascade).await?;
+        self.drop_data(&[id], &tables).await
+    }
+
+    /// Deletes a feature, and drops its column from the online and offline tables of its group.
+    pub async fn delete_feature(&self, id: i64) -> Result<()> {
+        let feature = self
+            .metadata
+            .get_feature(GetOpt::ID(id))
+            .await?
+            .ok_or_else(|| Error::DataNotFound(format!("feature not found by id {id}")))?;
+        let tables = self.data_tables(&[feature.group_id]).await?;
+
+        self.metadata.delete_feature(id).await?;
+        self.drop_column(&feature, &tables).await
+    }
+
     pub async fn list_entity<'a>(&self, opt: ListOpt<'a>) -> Result<Vec<Entity>> {
         self.metadata.list_entity(opt).await.map_err(|e| e.into())
     }
@@ -105,16 +309,16 @@ impl Store {
             .list_feature(feature_names)
             .await?
             .into_iter()
-            .map(|f| RichFeature::from2(f))
+            .map(RichFeature::from2)
             .collect())
     }
 
     pub async fn list_feature(&self, feature_names: &[String]) -> Result<Vec<Feature>> {
         let features = self
             .metadata
-            .list_feature(ListFeatureOpt::EntityIDs(vec![1]))
+            .list_feature(ListFeatureOpt::All)
             .await
-            .map_err(|e| Error::from(e))?;
+            .map_err(Error::from)?;
 
         let features = if feature_names.is_empty() {
             features
@@ -127,8 +331,976 @@ impl Store {
 
         Ok(features)
     }
+
+    pub async fn get_revision(&self, opt: GetRevisionOpt) -> Result<Option<Revision>> {
+        self.metadata.get_revision(opt).await.map_err(|e| e.into())
+    }
+
+    pub async fn list_revision(&self, opt: ListRevisionOpt) -> Result<Vec<Revision>> {
+        self.metadata.list_revision(opt).await.map_err(|e| e.into())
+    }
+
+    /// Gets the online values of features, given by full names like `device.model`,
+    /// for one entity key.
+    pub async fn get_online_features(
+        &self,
+        entity_key: &str,
+        feature_names: &[String],
+    ) -> Result<FeatureValues> {
+        let online = self.online()?;
+        let features = self.resolve_features(feature_names).await?;
+
+        let mut values = vec![None; features.len()];
+        for (group_id, (positions, group_features)) in split_by_group(&features) {
+            let group_values = online.get(group_id, entity_key, &group_features).await?;
+            for (i, value) in positions.into_iter().zip(group_values) {
+                values[i] = value;
+            }
+        }
+
+        Ok(FeatureValues {
+            entity_key: entity_key.to_owned(),
+            features: feature_names.to_vec(),
+            values,
+        })
+    }
+
+    /// Gets the online values of features for many entity keys, issuing one query per group.
+    pub async fn get_online_features_multi(
+        &self,
+        entity_keys: &[String],
+        feature_names: &[String],
+    ) -> Result<HashMap<String, FeatureValues>> {
+        let online = self.online()?;
+        let features = self.resolve_features(feature_names).await?;
+
+        let mut res: HashMap<String, FeatureValues> = entity_keys
+            .iter()
+            .map(|key| {
+                let values = FeatureValues {
+                    entity_key: key.to_owned(),
+                    features: feature_names.to_vec(),
+                    values: vec![None; features.len()],
+                };
+                (key.to_owned(), values)
+            })
+            .collect();
+
+        for (group_id, (positions, group_features)) in split_by_group(&features) {
+            let group_values = online
+                .multi_get(group_id, entity_keys, &group_features)
+                .await?;
+            for (key, values) in group_values {
+                if let Some(row) = res.get_mut(&key) {
+                    for (&i, value) in positions.iter().zip(values) {
+                        row.values[i] = value;
+                    }
+                }
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Imports rows of a batch group into the offline store as a new revision.
+    pub async fn import<I, E>(&self, opt: ImportOpt, rows: I) -> Result<ImportResult>
+    where
+        I: IntoIterator<Item = std::result::Result<Row, E>>,
+        E: Display,
+    {
+        let offline = self.offline()?;
+
+        let group = self.get_batch_group(&opt.group_name).await?;
+
+        let group_features = self
+            .metadata
+            .list_feature(ListFeatureOpt::GroupIDs(vec![group.id]))
+            .await?;
+        let mut features: Vec<Feature> = Vec::with_capacity(opt.features.len());
+        for name in &opt.features {
+            if features.iter().any(|f| &f.name == name) {
+                return Err(Error::InvalidValue(format!(
+                    "feature '{name}' is duplicated"
+                )));
+            }
+            match group_features.iter().find(|f| &f.name == name) {
+                Some(f) => features.push(f.clone()),
+                None => {
+                    return Err(Error::DataNotFound(format!(
+                        "feature '{name}' not found in group '{}'",
+                        group.name
+                    )))
+                }
+            }
+        }
+
+        let revision = opt
+            .revision
+            .unwrap_or_else(|| Utc::now().timestamp_millis());
+        let data_table = format!("offline_batch_{}_{}", group.id, revision);
+        let exists = self
+            .metadata
+            .get_revision(GetRevisionOpt::Revision {
+                group_id: group.id,
+                revision,
+            })
+            .await?
+            .is_some();
+        if exists {
+            return Err(Error::DataExist(format!(
+                "revision {revision} of group '{}' already exists",
+                group.name
+            )));
+        }
+        // a data table without a revision is left by an import interrupted halfway,
+        // it's never read and is replaced
+        offline
+            .drop_tables(std::slice::from_ref(&data_table))
+            .await?;
+
+        let rows = offline
+            .import(&data_table, &features, rows)
+            .await
+            .map_err(|e| match e {
+                DatabaseError::ColumnAlreadyExist(_) => Error::DataExist(format!(
+                    "revision {revision} of group '{}' already exists",
+                    group.name
+                )),
+                e => e.into(),
+            })?;
+        let revision_id = match self
+            .metadata
+            .create_revision(CreateRevisionOpt {
+                group_id: group.id,
+                revision,
+                data_table: data_table.clone(),
+                anchored: opt.revision.is_some(),
+                description: opt.description,
+                watermark: None,
+            })
+            .await
+        {
+            Ok(id) => id,
+            Err(err) => {
+                // the data table is of no use without its revision, and would block a retry
+                offline.drop_tables(&[data_table]).await?;
+                return Err(err.into());
+            }
+        };
+
+        Ok(ImportResult {
+            revision_id,
+            revision,
+            rows,
+        })
+    }
+
+    /// Syncs a revision of a batch group, the latest one by default, from the
+    /// offline store to the online store. Syncing the same revision again is safe.
+    pub async fn sync(&self, group_name: &str, revision: Option<i64>) -> Result<SyncResult> {
+        let online = self.online()?;
+        let offline = self.offline()?;
+
+        let group = self.get_batch_group(group_name).await?;
+        let revision = match revision {
+            Some(revision) => self
+                .metadata
+                .get_revision(GetRevisionOpt::Revision {
+                    group_id: group.id,
+                    revision,
+                })
+                .await?
+                .ok_or_else(|| {
+                    Error::DataNotFound(format!(
+                        "revision {revision} of group '{}' not found",
+                        group.name
+                    ))
+                })?,
+            None => self
+                .metadata
+                .list_revision(ListRevisionOpt::GroupIDs(vec![group.id]))
+                .await?
+                .pop()
+                .ok_or_else(|| {
+                    Error::DataNotFound(format!("group '{}' has no revision", group.name))
+                })?,
+        };
+
+        let features = self
+            .metadata
+            .list_feature(ListFeatureOpt::GroupIDs(vec![group.id]))
+            .await?;
+        online.create_table(group.id, &features).await?;
+
+        let rows = offline.export(revision.data_table.clone(), features.clone());
+        let rows = online.import(group.id, &features, rows).await?;
+        self.metadata
+            .update_online_revision(group.id, revision.id)
+            .await?;
+
+        Ok(SyncResult {
+            revision_id: revision.id,
+            revision: revision.revision,
+            rows,
+        })
+    }
+
+    /// Pushes the values of all features of an entity key in a stream group, a partial
+    /// push is rejected. The online store keeps the values with the latest event time,
+    /// and the offline stream log keeps all of them for joins.
+    pub async fn push(&self, opt: PushOpt) -> Result<()> {
+        let online = self.online()?;
+        let offline = self.offline()?;
+
+        let group = self
+            .metadata
+            .get_group(GetOpt::Name(&opt.group_name))
+            .await?
+            .ok_or_else(|| Error::DataNotFound(format!("group '{}' not found", opt.group_name)))?;
+        if group.category != Category::Stream {
+            return Err(Error::InvalidValue(format!(
+                "group '{}' is not a stream group",
+                group.name
+            )));
+        }
+        if opt.features.len() != opt.values.len() {
+            return Err(Error::InvalidValue(format!(
+                "expected {} values, got {}",
+                opt.features.len(),
+                opt.values.len()
+            )));
+        }
+
+        let features = self
+            .metadata
+            .list_feature(ListFeatureOpt::GroupIDs(vec![group.id]))
+            .await?;
+        let mut values = vec![None; features.len()];
+        for (i, (name, value)) in opt.features.iter().zip(opt.values).enumerate() {
+            if opt.features[..i].contains(name) {
+                return Err(Error::InvalidValue(format!(
+                    "feature '{name}' is duplicated"
+                )));
+            }
+            let position = features
+                .iter()
+                .position(|f| &f.name == name)
+                .ok_or_else(|| {
+                    Error::DataNotFound(format!(
+                        "feature '{name}' not found in group '{}'",
+                        group.name
+                    ))
+                })?;
+
+            let feature = &features[position];
+            if let Some(ref value) = value {
+                let invalid = |reason| DatabaseError::InvalidValue {
+                    feature: feature.full_name(),
+                    value: value.to_string(),
+                    reason,
+                };
+                if value.value_type() != feature.value_type {
+                    return Err(invalid(format!(
+                        "expected {}, got {}",
+                        feature.value_type,
+                        value.value_type()
+                    ))
+                    .into());
+                }
+                value.check().map_err(invalid)?;
+            }
+            values[position] = value;
+        }
+        // a partial push would null the left out features, both online and in the log
+        if opt.features.len() != features.len() {
+            let missing = features
+                .iter()
+                .filter(|f| !opt.features.contains(&f.name))
+                .map(|f| format!("'{}'", f.name))
+                .join(", ");
+            return Err(Error::InvalidValue(format!(
+                "features {missing} of group '{}' are not pushed, every feature is pushed at once",
+                group.name
+            )));
+        }
+
+        let unix_milli = opt
+            .unix_milli
+            .unwrap_or_else(|| Utc::now().timestamp_millis());
+        let row = Row {
+            entity_key: opt.entity_key,
+            values,
+        };
+        // a group registered before the stores were configured has no tables yet
+        online.create_table(group.id, &features).await?;
+        offline
+            .create_stream_table(&stream_table(group.id), &features)
+            .await?;
+        offline
+            .push(&stream_table(group.id), &features, row.clone(), unix_milli)
+            .await?;
+        online.push(group.id, &features, row, unix_milli).await?;
+        Ok(())
+    }
+
+    /// Compacts the log of a stream group into snapshots, each recorded as a revision,
+    /// at every multiple of `snapshot_interval` seconds having pushes since the latest
+    /// snapshot, up to the latest multiple reached by event time. Each snapshot is built
+    /// from the previous one and the rows pushed since, late events included. Returns
+    /// the snapshots taken, none if there is nothing new to compact.
+    pub async fn snapshot(&self, group_name: &str) -> Result<Vec<SnapshotResult>> {
+        let offline = self.offline()?;
+
+        let group = self
+            .metadata
+            .get_group(GetOpt::Name(group_name))
+            .await?
+            .ok_or_else(|| Error::DataNotFound(format!("group '{group_name}' not found")))?;
+        if group.category != Category::Stream {
+            return Err(Error::InvalidValue(format!(
+                "group '{group_name}' is not a stream group"
+            )));
+        }
+        let interval = match group.snapshot_interval {
+            Some(interval) if interval > 0 => i64::from(interval) * 1000,
+            _ => {
+                return Err(Error::InvalidValue(format!(
+                    "group '{group_name}' has no snapshot interval"
+                )))
+            }
+        };
+
+        let stream_table = stream_table(group.id);
+        let last = match offline.max_unix_milli(&stream_table).await? {
+            Some(unix_milli) => unix_milli.div_euclid(interval) * interval,
+            None => return Ok(Vec::new()),
+        };
+        let mut base = self
+            .metadata
+            .list_revision(ListRevisionOpt::GroupIDs(vec![group.id]))
+            .await?
+            .pop();
+
+        let features = self
+            .metadata
+            .list_feature(ListFeatureOpt::GroupIDs(vec![group.id]))
+            .await?;
+        let mut results = Vec::new();
+        loop {
+            let after = base.as_ref().map_or(i64::MIN, |r| r.revision);
+            // the first multiple of the interval at or after the next event time
+            let revision = match offline.next_unix_milli(&stream_table, after).await? {
+                Some(unix_milli) => -(-unix_milli).div_euclid(interval) * interval,
+                None => break,
+            };
+            if revision > last {
+                break;
+            }
+
+            let data_table = format!("offline_snapshot_{}_{}", group.id, revision);
+            // a snapshot table without a revision is left by a snapshot interrupted halfway
+            offline
+                .drop_tables(std::slice::from_ref(&data_table))
+                .await?;
+            let (rows, watermark) = offline
+                .snapshot(
+                    &stream_table,
+                    base.as_ref().map(snapshot_base),
+                    &data_table,
+                    &features,
+                    revision,
+                )
+                .await?;
+            let revision_id = match self
+                .metadata
+                .create_revision(CreateRevisionOpt {
+                    group_id: group.id,
+                    revision,
+                    data_table: data_table.clone(),
+                    anchored: true,
+                    description: "snapshot".to_owned(),
+                    watermark: Some(watermark),
+                })
+                .await
+            {
+                Ok(id) => id,
+                Err(err) => {
+                    offline.drop_tables(&[data_table]).await?;
+                    return Err(err.into());
+                }
+            };
+
+            results.push(SnapshotResult {
+                revision_id,
+                revision,
+                rows,
+            });
+            base = self
+                .metadata
+                .get_revision(GetRevisionOpt::ID(revision_id))
+                .await?;
+        }
+
+        Ok(results)
+    }
+
+    /// Joins features to entity rows by point-in-time. For each entity row, a batch
+    /// group takes values from its latest revision at or before `unix_milli`, and a
+    /// stream group takes the latest values pushed at or before `unix_milli`, from its
+    /// latest snapshot and the log after it. So nothing leaks from the future.
+    pub async fn join<I, E>(&self, opt: JoinOpt, entity_rows: I) -> Result<JoinResult>
+    where
+        I: IntoIterator<Item = std::result::Result<EntityRow, E>>,
+        E: Display,
+    {
+        let offline = self.offline()?;
+        let features = self.resolve_features(&opt.features).await?;
+
+        let group_ids: Vec<i64> = features.iter().map(|f| f.group_id).unique().collect();
+        let mut sources: BTreeMap<i64, JoinSource> = BTreeMap::new();
+        for group in self
+            .metadata
+            .list_group(ListOpt::IDs(group_ids.clone()))
+            .await?
+        {
+            let stream_table = match group.category {
+                Category::Batch => None,
+                Category::Stream => Some(stream_table(group.id)),
+            };
+            sources.insert(
+                group.id,
+                JoinSource {
+                    revisions: Vec::new(),
+                    stream_table,
+                },
+            );
+        }
+
+        let all_revisions = self
+            .metadata
+            .list_revision(ListRevisionOpt::GroupIDs(group_ids))
+            .await?;
+        for (i, revision) in all_revisions.iter().enumerate() {
+            // a revision is valid until the next revision of the same group
+            let end = match all_revisions.get(i + 1) {
+                Some(next) if next.group_id == revision.group_id => next.revision,
+                _ => i64::MAX,
+            };
+            sources
+                .entry(revision.group_id)
+                .or_default()
+                .revisions
+                .push(RevisionRange {
+                    data_table: revision.data_table.clone(),
+                    start: revision.revision,
+                    end,
+                    watermark: snapshot_base(revision).watermark,
+                });
+        }
+
+        let rows = offline
+            .join(&features, &sources, opt.value_names.len(), entity_rows)
+            .await?;
+
+        let header = ["entity_key".to_owned(), "unix_milli".to_owned()]
+            .into_iter()
+            .chain(opt.value_names)
+            .chain(features.iter().map(Feature::full_name))
+            .collect();
+        Ok(JoinResult {
+            header,
+            rows: Box::pin(rows.map_err(Error::from)),
+        })
+    }
+
+    /// Exports the rows of a group as of `unix_milli`. A batch group exports its latest
+    /// revision at or before it, and a stream group the latest values pushed at or
+    /// before it, from its latest snapshot and the log after it.
+    pub async fn export(&self, opt: ExportOpt) -> Result<ExportResult> {
+        let offline = self.offline()?;
+
+        let group = self
+            .metadata
+            .get_group(GetOpt::Name(&opt.group_name))
+            .await?
+            .ok_or_else(|| Error::DataNotFound(format!("group '{}' not found", opt.group_name)))?;
+        let entity = self
+            .metadata
+            .get_entity(GetOpt::ID(group.entity_id))
+            .await?
+            .ok_or_else(|| Error::DataNotFound(format!("entity {} not found", group.entity_id)))?;
+
+        let all_features = self
+            .metadata
+            .list_feature(ListFeatureOpt::GroupIDs(vec![group.id]))
+            .await?;
+        let features = match opt.features.is_empty() {
+            true => all_features,
+            false => opt
+                .features
+                .iter()
+                .map(|name| {
+                    all_features
+                        .iter()
+                        .find(|f| &f.name == name)
+                        .cloned()
+                        .ok_or_else(|| {
+                            Error::DataNotFound(format!(
+                                "feature '{name}' not found in group '{}'",
+                                group.name
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?,
+        };
+
+        let unix_milli = opt
+            .unix_milli
+            .unwrap_or_else(|| Utc::now().timestamp_millis());
+        let revision = self
+            .metadata
+            .list_revision(ListRevisionOpt::GroupIDs(vec![group.id]))
+            .await?
+            .into_iter()
+            .take_while(|r| r.revision <= unix_milli)
+            .last();
+
+        let rows = match group.category {
+            Category::Batch => {
+                let revision = revision.as_ref().ok_or_else(|| {
+                    Error::DataNotFound(format!(
+                        "group '{}' has no revision at {unix_milli}",
+                        group.name
+                    ))
+                })?;
+                offline.export(revision.data_table.clone(), features.clone())
+            }
+            Category::Stream => {
+                offline
+                    .export_stream(
+                        &stream_table(group.id),
+                        revision.as_ref().map(snapshot_base),
+                        &features,
+                        unix_milli,
+                    )
+                    .await?
+            }
+        };
+
+        let header = std::iter::once(entity.name)
+            .chain(features.into_iter().map(|f| f.name))
+            .collect();
+        Ok(ExportResult {
+            revision: revision.map(|r| r.revision),
+            header,
+            rows: Box::pin(rows.map_err(Error::from)),
+        })
+    }
+
+    fn online(&self) -> Result<&OnlineStore> {
+        self.online
+            .as_ref()
+            .ok_or_else(|| "online store is not configured".into())
+    }
+
+    fn offline(&self) -> Result<&OfflineStore> {
+        self.offline
+            .as_ref()
+            .ok_or_else(|| "offline store is not configured".into())
+    }
+
+    /// The offline tables of groups: data tables of their revisions and stream logs.
+    async fn data_tables(&self, group_ids: &[i64]) -> Result<Vec<String>> {
+        let revisions = self
+            .metadata
+            .list_revision(ListRevisionOpt::GroupIDs(group_ids.to_vec()))
+            .await?;
+        Ok(revisions
+            .into_iter()
+            .map(|r| r.data_table)
+            .chain(group_ids.iter().map(|&id| stream_table(id)))
+            .collect())
+    }
+
+    async fn drop_data(&self, group_ids: &[i64], tables: &[String]) -> Result<()> {
+        if let Some(ref online) = self.online {
+            for &group_id in group_ids {
+                online.drop_table(group_id).await?;
+            }
+        }
+        if let Some(ref offline) = self.offline {
+            offline.drop_tables(tables).await?;
+        }
+        Ok(())
+    }
+
+    async fn drop_column(&self, feature: &Feature, tables: &[String]) -> Result<()> {
+        if let Some(ref online) = self.online {
+            online.drop_column(feature.group_id, &feature.name).await?;
+        }
+        if let Some(ref offline) = self.offline {
+            offline.drop_column(tables, &feature.name).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_batch_group(&self, name: &str) -> Result<Group> {
+        let group = self
+            .metadata
+            .get_group(GetOpt::Name(name))
+            .await?
+            .ok_or_else(|| Error::DataNotFound(format!("group '{name}' not found")))?;
+        if group.category != Category::Batch {
+            return Err(Error::InvalidValue(format!(
+                "group '{name}' is not a batch group"
+            )));
+        }
+        Ok(group)
+    }
+
+    /// Looks up features by full names, keeping the order of `feature_names`.
+    async fn resolve_features(&self, feature_names: &[String]) -> Result<Vec<Feature>> {
+        if feature_names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let features = self.list_feature(feature_names).await?;
+        feature_names
+            .iter()
+            .map(|name| {
+                features
+                    .iter()
+                    .find(|f| &f.full_name() == name)
+                    .cloned()
+                    .ok_or_else(|| Error::DataNotFound(format!("feature '{name}' not found")))
+            })
+            .collect()
+    }
+}
+
+/// The offline log of a stream group.
+fn stream_table(group_id: i64) -> String {
+    format!("offline_stream_{group_id}")
+}
+
+fn snapshot_base(revision: &Revision) -> SnapshotBase<'_> {
+    SnapshotBase {
+        data_table: &revision.data_table,
+        unix_milli: revision.revision,
+        // a snapshot taken before watermarks were kept has merged no late events
+        watermark: revision.watermark.unwrap_or(i64::MAX),
+    }
+}
+
+/// Splits features by group id, remembering the position of each feature.
+fn split_by_group(features: &[Feature]) -> BTreeMap<i64, (Vec<usize>, Vec<Feature>)> {
+    let mut groups: BTreeMap<i64, (Vec<usize>, Vec<Feature>)> = BTreeMap::new();
+    for (i, feature) in features.iter().enumerate() {
+        let (positions, group_features) = groups.entry(feature.group_id).or_default();
+        positions.push(i);
+        group_features.push(feature.clone());
+    }
+    groups
 }
 
 fn is_contains(name: &str, names: &[String]) -> bool {
     names.iter().any(|n| n == name)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sqlx::SqlitePool;
+    use tempfile::TempDir;
+
+    use crate::database::metadata::FeatureValue;
+    use crate::database::SQLiteOpt;
+    use crate::feastore::BackendOpt;
+
+    const METADATA: &str = r#"
+kind: Entity
+name: device
+description: 'device'
+---
+kind: Group
+name: phone
+entity: device
+category: batch
+description: 'phone'
+---
+kind: Feature
+name: price
+group: phone
+value-type: int64
+description: 'price'
+---
+kind: Group
+name: click
+entity: device
+category: stream
+snapshot-interval: 1
+description: 'click'
+features:
+- name: clicks
+  value-type: int64
+  description: 'clicks'
+"#;
+
+    async fn open(dir: &TempDir) -> Store {
+        let backend = |file: &str| {
+            let db_file = dir.path().join(file);
+            std::fs::File::create(&db_file).unwrap();
+            BackendOpt {
+                sqlite: Some(SQLiteOpt {
+                    db_file: db_file.to_string_lossy().into_owned(),
+                }),
+            }
+        };
+
+        let store = Store::open(FeatureStoreConfig {
+            metadata: backend("metadata.db"),
+            online: Some(backend("online.db")),
+            offline: Some(backend("offline.db")),
+        })
+        .await;
+        store.apply(METADATA.as_bytes()).await.unwrap();
+        store
+    }
+
+    async fn connect(dir: &TempDir, file: &str) -> SqlitePool {
+        let db_file = dir.path().join(file);
+        SqlitePool::connect(&format!("sqlite://{}", db_file.display()))
+            .await
+            .unwrap()
+    }
+
+    async fn tables(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn import(store: &Store, revision: i64) -> Result<ImportResult> {
+        let rows = [("1", 3999), ("2", 5299)].map(|(key, price)| {
+            Ok::<_, String>(Row {
+                entity_key: key.to_owned(),
+                values: vec![Some(FeatureValue::Int64(price))],
+            })
+        });
+        store
+            .import(
+                ImportOpt {
+                    group_name: "phone".to_owned(),
+                    features: vec!["price".to_owned()],
+                    revision: Some(revision),
+                    description: String::new(),
+                },
+                rows,
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn import_failing_to_create_revision() {
+        let dir = TempDir::new().unwrap();
+        let store = open(&dir).await;
+        let metadata = connect(&dir, "metadata.db").await;
+        let offline = connect(&dir, "offline.db").await;
+
+        sqlx::query("CREATE TRIGGER fail BEFORE INSERT ON revision BEGIN SELECT RAISE(ABORT, 'failed'); END")
+            .execute(&metadata)
+            .await
+            .unwrap();
+        assert!(import(&store, 1000).await.is_err());
+        // the data table goes with the failed revision
+        assert_eq!(tables(&offline).await, vec!["offline_stream_2"]);
+
+        sqlx::query("DROP TRIGGER fail")
+            .execute(&metadata)
+            .await
+            .unwrap();
+        let res = import(&store, 1000).await.unwrap();
+        assert_eq!((res.revision, res.rows), (1000, 2));
+        assert_eq!(
+            tables(&offline).await,
+            vec!["offline_batch_1_1000", "offline_stream_2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn import_replacing_data_table_without_revision() {
+        let dir = TempDir::new().unwrap();
+        let store = open(&dir).await;
+        let offline = connect(&dir, "offline.db").await;
+
+        sqlx::query("CREATE TABLE offline_batch_1_1000 (entity_key TEXT NOT NULL PRIMARY KEY)")
+            .execute(&offline)
+            .await
+            .unwrap();
+        let res = import(&store, 1000).await.unwrap();
+        assert_eq!((res.revision, res.rows), (1000, 2));
+
+        let revisions = store
+            .list_revision(ListRevisionOpt::GroupIDs(vec![1]))
+            .await
+            .unwrap();
+        assert_eq!(revisions.len(), 1);
+        let res = store.sync("phone", None).await.unwrap();
+        assert_eq!((res.revision, res.rows), (1000, 2));
+
+        // a recorded revision isn't replaced
+        assert!(import(&store, 1000)
+            .await
+            .is_err_and(|e| matches!(e, Error::DataExist(_))));
+        let res = store.sync("phone", Some(1000)).await.unwrap();
+        assert_eq!(res.rows, 2);
+    }
+
+    async fn push(store: &Store, clicks: i64, unix_milli: i64) {
+        store
+            .push(PushOpt {
+                entity_key: "1".to_owned(),
+                group_name: "click".to_owned(),
+                features: vec!["clicks".to_owned()],
+                values: vec![Some(FeatureValue::Int64(clicks))],
+                unix_milli: Some(unix_milli),
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn get_online_features_never_synced() {
+        let dir = TempDir::new().unwrap();
+        let store = open(&dir).await;
+        let online = connect(&dir, "online.db").await;
+
+        // as if the group was registered before the online store was configured
+        sqlx::query("DROP TABLE online_1")
+            .execute(&online)
+            .await
+            .unwrap();
+
+        let features = vec!["phone.price".to_owned()];
+        let values = store.get_online_features("1", &features).await.unwrap();
+        assert_eq!(values.values, vec![None]);
+
+        let keys = vec!["1".to_owned(), "2".to_owned()];
+        let values = store
+            .get_online_features_multi(&keys, &features)
+            .await
+            .unwrap();
+        assert_eq!(values["1"].values, vec![None]);
+        assert_eq!(values["2"].values, vec![None]);
+    }
+
+    #[tokio::test]
+    async fn push_creating_tables() {
+        let dir = TempDir::new().unwrap();
+        let store = open(&dir).await;
+        let online = connect(&dir, "online.db").await;
+        let offline = connect(&dir, "offline.db").await;
+
+        // as if the group was registered before the stores were configured
+        sqlx::query("DROP TABLE online_2")
+            .execute(&online)
+            .await
+            .unwrap();
+        sqlx::query("DROP TABLE offline_stream_2")
+            .execute(&offline)
+            .await
+            .unwrap();
+
+        push(&store, 1, 100).await;
+        let values = store
+            .get_online_features("1", &["click.clicks".to_owned()])
+            .await
+            .unwrap();
+        assert_eq!(values.values, vec![Some(FeatureValue::Int64(1))]);
+        assert_eq!(tables(&offline).await, vec!["offline_stream_2"]);
+    }
+
+    #[tokio::test]
+    async fn snapshot_every_interval() {
+        let dir = TempDir::new().unwrap();
+        let store = open(&dir).await;
+        let revisions = |results: Vec<SnapshotResult>| {
+            results
+                .into_iter()
+                .map(|r| (r.revision, r.rows))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(store.snapshot("click").await.unwrap().is_empty());
+
+        for (clicks, unix_milli) in [(1, 500), (2, 1500), (3, 3500), (4, 4200)] {
+            push(&store, clicks, unix_milli).await;
+        }
+        // no snapshot at 3000 without pushes since 2000, nor at 5000 not reached yet
+        assert_eq!(
+            revisions(store.snapshot("click").await.unwrap()),
+            vec![(1000, 1), (2000, 1), (4000, 1)]
+        );
+        assert!(store.snapshot("click").await.unwrap().is_empty());
+
+        push(&store, 5, 5000).await;
+        assert_eq!(
+            revisions(store.snapshot("click").await.unwrap()),
+            vec![(5000, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn join_and_export_late_event() {
+        let dir = TempDir::new().unwrap();
+        let store = open(&dir).await;
+        let clicks = |row: Vec<Option<FeatureValue>>| row.last().cloned().flatten();
+
+        push(&store, 1, 4500).await;
+        push(&store, 3, 6000).await;
+        assert_eq!(store.snapshot("click").await.unwrap().len(), 2);
+        // event time before the snapshot at 5000, but pushed after it was taken
+        push(&store, 2, 4800).await;
+
+        let entity_rows = [("1", 4600), ("1", 5500)].map(|(key, unix_milli)| {
+            Ok::<_, String>(EntityRow {
+                entity_key: key.to_owned(),
+                unix_milli,
+                values: vec![],
+            })
+        });
+        let joined: Vec<_> = store
+            .join(
+                JoinOpt {
+                    features: vec!["click.clicks".to_owned()],
+                    value_names: vec![],
+                },
+                entity_rows,
+            )
+            .await
+            .unwrap()
+            .rows
+            .map_ok(clicks)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(
+            joined,
+            vec![Some(FeatureValue::Int64(1)), Some(FeatureValue::Int64(2))]
+        );
+
+        let exported = store
+            .export(ExportOpt {
+                group_name: "click".to_owned(),
+                features: vec![],
+                unix_milli: Some(5500),
+            })
+            .await
+            .unwrap();
+        assert_eq!(exported.revision, Some(5000));
+        let rows: Vec<_> = exported.rows.try_collect().await.unwrap();
+        assert_eq!(rows[0].values, vec![Some(FeatureValue::Int64(2))]);
+    }
+}
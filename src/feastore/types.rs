@@ -0,0 +1,131 @@
+use std::io;
+
+use futures::stream::BoxStream;
+use futures::{Stream, TryStreamExt};
+use serde::Serialize;
+
+use crate::database::metadata::FeatureValue;
+use crate::feastore::Result;
+
+/// Feature values of one entity key, in the same order as the requested features.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeatureValues {
+    pub entity_key: String,
+    /// full names of the features, e.g. `device.model`
+    pub features: Vec<String>,
+    /// `None` means the value is null or the entity key is missing
+    pub values: Vec<Option<FeatureValue>>,
+}
+
+impl FeatureValues {
+    pub fn get(&self, full_name: &str) -> Option<&FeatureValue> {
+        self.features
+            .iter()
+            .position(|f| f == full_name)
+            .and_then(|i| self.values[i].as_ref())
+    }
+}
+
+/// A row of feature values of one entity key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub entity_key: String,
+    pub values: Vec<Option<FeatureValue>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportResult {
+    pub revision_id: i64,
+    pub revision: i64,
+    pub rows: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncResult {
+    pub revision_id: i64,
+    pub revision: i64,
+    pub rows: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotResult {
+    pub revision_id: i64,
+    pub revision: i64,
+    pub rows: u64,
+}
+
+/// A row to join features to, identified by an entity key and a point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityRow {
+    pub entity_key: String,
+    pub unix_milli: i64,
+    /// extra values carried to the output as is, e.g. labels
+    pub values: Vec<String>,
+}
+
+/// The result of a point-in-time join.
+pub struct JoinResult {
+    /// `entity_key`, `unix_milli`, names of the extra values and full names of the features
+    pub header: Vec<String>,
+    /// joined rows in the order of the entity rows, with values in the order of `header`
+    pub rows: BoxStream<'static, Result<Vec<Option<FeatureValue>>>>,
+}
+
+impl JoinResult {
+    /// Writes the header and all rows as CSV, a null is written as an empty field.
+    /// Returns the number of written rows.
+    pub async fn write_csv<W: io::Write>(self, w: W) -> Result<u64> {
+        let rows = self.rows.map_ok(|row| row.iter().map(field).collect());
+        write_csv(w, &self.header, rows).await
+    }
+}
+
+/// The rows of a group as of a point in time.
+pub struct ExportResult {
+    /// the revision or snapshot exported, for a stream group the log after it is
+    /// replayed on top of it; `None` if a stream group has no snapshot by then
+    pub revision: Option<i64>,
+    /// name of the entity and names of the features, the same as an import file,
+    /// see [`ExportResult::write_csv`] for how nulls are imported back
+    pub header: Vec<String>,
+    /// rows ordered by entity key, with values in the order of `header`
+    pub rows: BoxStream<'static, Result<Row>>,
+}
+
+impl ExportResult {
+    /// Writes the header and all rows as CSV, a null is written as an empty field.
+    /// Returns the number of written rows.
+    ///
+    /// The output can be imported again, but CSV has no null string: import reads an
+    /// empty field as an empty string for a string feature, and as null otherwise.
+    pub async fn write_csv<W: io::Write>(self, w: W) -> Result<u64> {
+        let rows = self.rows.map_ok(|row| {
+            std::iter::once(row.entity_key)
+                .chain(row.values.iter().map(field))
+                .collect()
+        });
+        write_csv(w, &self.header, rows).await
+    }
+}
+
+fn field(value: &Option<FeatureValue>) -> String {
+    value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+}
+
+async fn write_csv<W, S>(w: W, header: &[String], mut records: S) -> Result<u64>
+where
+    W: io::Write,
+    S: Stream<Item = Result<Vec<String>>> + Unpin,
+{
+    let mut writer = csv::Writer::from_writer(w);
+    writer.write_record(header).map_err(|e| e.to_string())?;
+
+    let mut count = 0;
+    while let Some(record) = records.try_next().await? {
+        writer.write_record(&record).map_err(|e| e.to_string())?;
+        count += 1;
+    }
+
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(count)
+}
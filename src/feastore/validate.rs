@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use toml_edit::{ImDocument, Item, TableLike};
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser, Tag};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+/// A problem of an apply manifest, located in its source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// the manifest file, none for a manifest read from elsewhere
+    pub file: Option<String>,
+    /// the number of the document, counted from 1
+    pub document: usize,
+    /// the line of the problem, counted from 1
+    pub line: usize,
+    /// the column of the problem, counted from 1
+    pub column: usize,
+    /// the path of the faulty field, e.g. `items[3].groups[0].features[1].value-type`,
+    /// empty for the document itself
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}: ")?;
+        }
+        write!(
+            f,
+            "document {}, line {}, column {}: ",
+            self.document, self.line, self.column
+        )?;
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A problem of a manifest document, not located yet.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Problem {
+    pub path: String,
+    pub message: String,
+}
+
+impl Problem {
+    pub(crate) fn new(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Checks how the objects of a manifest document are declared, by their kinds or in
+/// its items. The objects are checked when they are deserialized.
+pub(crate) fn validate(document: &Value) -> Vec<Problem> {
+    let Some(mapping) = document.as_mapping() else {
+        return vec![Problem::new("", "missing kind or items")];
+    };
+    if mapping.contains_key("kind") {
+        return Vec::new();
+    }
+    let Some(items) = ["items", "Items"]
+        .into_iter()
+        .find(|k| mapping.contains_key(k))
+    else {
+        return vec![Problem::new("", "missing kind or items")];
+    };
+
+    let mut problems = Vec::new();
+    for key in mapping.keys() {
+        match key.as_str() {
+            Some(key) if key == items => {}
+            Some(key) => problems.push(Problem::new(
+                key,
+                format!("unknown field, expected {items}"),
+            )),
+            None => problems.push(Problem::new("", "expected string keys")),
+        }
+    }
+    match &mapping[items] {
+        Value::Sequence(objects) => {
+            for (i, object) in objects.iter().enumerate() {
+                if !object.is_mapping() {
+                    problems.push(Problem::new(&index(items, i), "expected a mapping"));
+                }
+            }
+        }
+        _ => problems.push(Problem::new(items, "expected a sequence")),
+    }
+    problems
+}
+
+/// Deserializes an object of a manifest, telling its first problem, at the path of the
+/// faulty field if any.
+pub(crate) fn deserialize<T: DeserializeOwned>(
+    path: &str,
+    value: Value,
+) -> std::result::Result<T, Problem> {
+    serde_path_to_error::deserialize(value).map_err(|err| {
+        let path = match err.path().to_string() {
+            field if field == "." => path.to_string(),
+            field => child(path, &field),
+        };
+        Problem::new(&path, err.into_inner().to_string())
+    })
+}
+
+pub(crate) fn child(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+pub(crate) fn index(path: &str, i: usize) -> String {
+    format!("{path}[{i}]")
+}
+
+/// `a.b[1]` -> `a.b`, `a.b` -> `a`, `a` -> the document itself
+fn parent(path: &str) -> &str {
+    path.rfind(['.', '[']).map_or("", |i| &path[..i])
+}
+
+/// A syntax error of a manifest, no document after it can be told apart.
+pub(crate) struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// The line and column of the nodes of each document of a manifest, by their path. A
+/// field of a mapping is located at its key.
+#[derive(Default)]
+pub(crate) struct Positions {
+    documents: Vec<HashMap<String, (usize, usize)>>,
+}
+
+impl Positions {
+    /// Parses a yaml manifest, or a json one being yaml as well, into its documents
+    /// along with their positions. Only the documents before a syntax error are kept.
+    pub(crate) fn parse(text: &str) -> (Vec<Value>, Self, Option<SyntaxError>) {
+        let mut loader = Loader::default();
+        let result = Parser::new_from_str(text).load(&mut loader, true);
+
+        // the parser stops at a syntax error, after any error found by the loader
+        let error = loader.error.or_else(|| {
+            let err = result.err()?;
+            let document = loader.positions.documents.len().max(1) - 1;
+            let error = SyntaxError {
+                line: err.marker().line(),
+                column: err.marker().col() + 1,
+                message: err.info().to_string(),
+            };
+            Some((document, error))
+        });
+        let mut documents = loader.documents;
+        if let Some((document, _)) = error {
+            documents.truncate(document);
+        }
+        (documents, loader.positions, error.map(|(_, err)| err))
+    }
+
+    /// Parses a toml manifest, which is a single document.
+    pub(crate) fn parse_toml(text: &str) -> Self {
+        let mut positions = Self::default();
+        positions.documents.push(HashMap::new());
+        if let Ok(document) = ImDocument::parse(text) {
+            positions.toml_table(text, "", document.as_table());
+        }
+        positions
+    }
+
+    fn toml_table(&mut self, text: &str, path: &str, table: &dyn TableLike) {
+        for (key, _) in table.iter() {
+            let Some((key, item)) = table.get_key_value(key) else {
+                continue;
+            };
+            let child = child(path, key.get());
+            if let Some(span) = key.span() {
+                // a mapping is located at its first key, unless it's a field
+                let position = line_column(text, span.start);
+                self.record(path.to_string(), position);
+                self.record(child.clone(), position);
+            }
+            self.toml_item(text, &child, item);
+        }
+    }
+
+    fn toml_item(&mut self, text: &str, path: &str, item: &Item) {
+        match item {
+            Item::Table(table) => self.toml_table(text, path, table),
+            Item::ArrayOfTables(tables) => {
+                for (i, table) in tables.iter().enumerate() {
+                    let path = index(path, i);
+                    if let Some(span) = table.span() {
+                        self.record(path.clone(), line_column(text, span.start));
+                    }
+                    self.toml_table(text, &path, table);
+                }
+            }
+            Item::Value(value) => self.toml_value(text, path, value),
+            Item::None => {}
+        }
+    }
+
+    fn toml_value(&mut self, text: &str, path: &str, value: &toml_edit::Value) {
+        match value {
+            toml_edit::Value::InlineTable(table) => self.toml_table(text, path, table),
+            toml_edit::Value::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    let path = index(path, i);
+                    if let Some(span) = value.span() {
+                        self.record(path.clone(), line_column(text, span.start));
+                    }
+                    self.toml_value(text, &path, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Locates a problem of a document, counted from 0, at its path or else at the
+    /// closest ancestor of it, e.g. the object missing a field.
+    pub(crate) fn locate(
+        &self,
+        file: Option<&str>,
+        document: usize,
+        problem: Problem,
+    ) -> ValidationError {
+        let nodes = self.documents.get(document);
+        let mut path = problem.path.as_str();
+        let (line, column) = loop {
+            if let Some(position) = nodes.and_then(|nodes| nodes.get(path)) {
+                break *position;
+            }
+            if path.is_empty() {
+                break (1, 1);
+            }
+            path = parent(path);
+        };
+
+        ValidationError {
+            file: file.map(str::to_string),
+            document: document + 1,
+            line,
+            column,
+            path: problem.path,
+            message: problem.message,
+        }
+    }
+
+    fn record(&mut self, path: String, position: (usize, usize)) {
+        if let Some(nodes) = self.documents.last_mut() {
+            nodes.entry(path).or_insert(position);
+        }
+    }
+}
+
+/// Builds the documents of a yaml text from its parsing events, recording the
+/// position of each node by its path.
+#[derive(Default)]
+struct Loader {
+    documents: Vec<Value>,
+    positions: Positions,
+    stack: Vec<Node>,
+    anchors: HashMap<usize, Value>,
+    /// the first error found in a document, counted from 0, which isn't a syntax error
+    /// of yaml itself, e.g. a duplicate key
+    error: Option<(usize, SyntaxError)>,
+}
+
+enum Node {
+    Mapping {
+        path: String,
+        anchor: usize,
+        mapping: Mapping,
+        /// the key of the next value, with its name in paths and its position
+        key: Option<(Value, String, (usize, usize))>,
+    },
+    Sequence {
+        path: String,
+        anchor: usize,
+        values: Vec<Value>,
+    },
+}
+
+impl Loader {
+    /// Tells the path of the next node, or `None` for a key of a mapping.
+    fn path(&self) -> Option<String> {
+        match self.stack.last() {
+            None => Some(String::new()),
+            Some(Node::Mapping { key: None, .. }) => None,
+            Some(Node::Mapping {
+                path,
+                key: Some((_, key, _)),
+                ..
+            }) => Some(child(path, key)),
+            Some(Node::Sequence { path, values, .. }) => Some(index(path, values.len())),
+        }
+    }
+
+    /// Records a scalar, which is a key of a mapping or a value.
+    fn scalar(&mut self, value: Value, name: String, anchor: usize, mark: Marker) {
+        let position = (mark.line(), mark.col() + 1);
+        match self.path() {
+            Some(path) => self.positions.record(path, position),
+            None => {
+                if let Some(Node::Mapping { path, .. }) = self.stack.last() {
+                    // a mapping is located at its first key, unless it's a field
+                    let (mapping, field) = (path.clone(), child(path, &name));
+                    self.positions.record(mapping, position);
+                    self.positions.record(field, position);
+                }
+            }
+        }
+        self.end(value, Some(name), anchor, position);
+    }
+
+    /// Adds a node having been read to its parent, or as a document.
+    fn end(&mut self, value: Value, name: Option<String>, anchor: usize, position: (usize, usize)) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, value.clone());
+        }
+        let document = self.positions.documents.len().max(1) - 1;
+        match self.stack.last_mut() {
+            None => self.documents.push(value),
+            Some(Node::Sequence { values, .. }) => values.push(value),
+            Some(Node::Mapping { mapping, key, .. }) => match key.take() {
+                // a complex key, which is no field
+                None => *key = Some((value, name.unwrap_or_else(|| "?".to_string()), position)),
+                Some((key, name, position)) => {
+                    if mapping.insert(key, value).is_some() && self.error.is_none() {
+                        let (line, column) = position;
+                        let message = format!("duplicate key '{name}'");
+                        self.error = Some((
+                            document,
+                            SyntaxError {
+                                line,
+                                column,
+                                message,
+                            },
+                        ));
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for Loader {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        let position = (mark.line(), mark.col() + 1);
+        match ev {
+            Event::DocumentStart => {
+                self.positions.documents.push(HashMap::new());
+                self.stack.clear();
+            }
+            Event::Scalar(text, style, anchor, tag) => {
+                let value = resolve(&text, style, tag.as_ref());
+                self.scalar(value, text, anchor, mark);
+            }
+            Event::Alias(anchor) => {
+                let value = self.anchors.get(&anchor).cloned().unwrap_or(Value::Null);
+                let name = value.as_str().unwrap_or("?").to_string();
+                self.scalar(value, name, 0, mark);
+            }
+            Event::MappingStart(anchor, _) => {
+                let path = self.path().unwrap_or_else(|| "?".to_string());
+                self.stack.push(Node::Mapping {
+                    path,
+                    anchor,
+                    mapping: Mapping::new(),
+                    key: None,
+                });
+            }
+            Event::SequenceStart(anchor, _) => {
+                let path = self.path().unwrap_or_else(|| "?".to_string());
+                self.positions.record(path.clone(), position);
+                self.stack.push(Node::Sequence {
+                    path,
+                    anchor,
+                    values: Vec::new(),
+                });
+            }
+            Event::MappingEnd | Event::SequenceEnd => match self.stack.pop() {
+                Some(Node::Mapping {
+                    anchor, mapping, ..
+                }) => self.end(Value::Mapping(mapping), None, anchor, position),
+                Some(Node::Sequence { anchor, values, .. }) => {
+                    self.end(Value::Sequence(values), None, anchor, position)
+                }
+                None => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Resolves a scalar by the yaml 1.2 core schema, like serde_yaml does.
+fn resolve(text: &str, style: TScalarStyle, tag: Option<&Tag>) -> Value {
+    let is_str = tag.is_some_and(|tag| tag.suffix == "str");
+    if style != TScalarStyle::Plain || is_str {
+        return Value::String(text.to_string());
+    }
+
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => {
+            return Value::Number(f64::INFINITY.into())
+        }
+        "-.inf" | "-.Inf" | "-.INF" => return Value::Number(f64::NEG_INFINITY.into()),
+        ".nan" | ".NaN" | ".NAN" => return Value::Number(f64::NAN.into()),
+        _ => {}
+    }
+
+    let int = match (text.strip_prefix("0x"), text.strip_prefix("0o")) {
+        (Some(hex), _) => i64::from_str_radix(hex, 16).ok(),
+        (_, Some(oct)) => i64::from_str_radix(oct, 8).ok(),
+        _ => text.parse::<i64>().ok(),
+    };
+    if let Some(int) = int {
+        return Value::Number(int.into());
+    }
+    // not the words rust parses as floats, e.g. `inf` or `nan`
+    let numeric = text
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E'));
+    match text.parse::<f64>() {
+        Ok(float) if numeric => Value::Number(float.into()),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Tells the line and column of a byte offset of a text, both counted from 1.
+pub(crate) fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
@@ -1,7 +1,29 @@
 pub mod database;
 mod feastore;
 
+pub use feastore::ApplyError;
+pub use feastore::ApplyPlan;
+pub use feastore::BackendOpt;
+pub use feastore::Change;
+pub use feastore::Conflict;
+pub use feastore::EntityRow;
 pub use feastore::Error;
+pub use feastore::ExportOpt;
+pub use feastore::ExportResult;
 pub use feastore::FeatureStoreConfig;
+pub use feastore::FeatureValues;
+pub use feastore::Format;
+pub use feastore::ImportOpt;
+pub use feastore::ImportResult;
+pub use feastore::JoinOpt;
+pub use feastore::JoinResult;
+pub use feastore::Manifest;
+pub use feastore::Prune;
+pub use feastore::PushOpt;
 pub use feastore::Result;
+pub use feastore::Row;
+pub use feastore::SnapshotResult;
 pub use feastore::Store;
+pub use feastore::SyncResult;
+pub use feastore::Update;
+pub use feastore::ValidationError;